@@ -4,12 +4,20 @@ extern crate psutil;
 
 #[cfg(not(test))]
 fn main() {
-    println!("{:>5} {:^5} {:>8} {:>8} {:.100}",
-        "PID", "STATE", "UTIME", "STIME", "CMD");
+    let mut processes = psutil::process::all().unwrap();
+    std::thread::sleep(std::time::Duration::from_millis(100));
 
-    for p in &psutil::process::all().unwrap() {
-        println!("{:>5} {:^5} {:>8.2} {:>8.2} {:.100}",
-            p.pid, p.state.to_string(), p.utime, p.stime,
+    println!("{:>5} {:^5} {:>8} {:>8} {:>5} {:.100}",
+        "PID", "STATE", "UTIME", "STIME", "%CPU", "CMD");
+
+    for p in processes.iter_mut().filter(|p| !p.is_kernel_thread()) {
+        // Skip processes that exited while sampling CPU usage
+        let cpu_percent = match p.cpu_percent() {
+            Ok(percent) => percent,
+            Err(_) => continue,
+        };
+        println!("{:>5} {:^5} {:>8.2} {:>8.2} {:>5.1} {:.100}",
+            p.pid, p.state.to_string(), p.utime, p.stime, cpu_percent,
             p.cmdline().unwrap().unwrap_or(format!("[{}]", p.comm)));
     }
 }
@@ -1,23 +1,32 @@
 //! Read process-specific information from `/proc`.
 
+use std::ffi::{CStr, OsStr, OsString};
 use std::fs::{self, read_dir, read_link};
-use std::os::unix::fs::MetadataExt;
 use std::io::{Error, ErrorKind, Result};
 use std::path::{Path, PathBuf};
 use std::str::FromStr;
 use std::string::ToString;
 use std::vec::Vec;
-use std::collections::HashMap;
-
-use libc::{_SC_CLK_TCK, _SC_PAGESIZE, SIGKILL};
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::mem;
+use std::num::ParseIntError;
+use std::os::unix::ffi::OsStrExt;
+use std::ptr;
+use std::thread;
+use std::time::{Duration, Instant, SystemTime};
+
+use libc::{self, _SC_CLK_TCK, _SC_PAGESIZE};
 use libc::{kill, sysconf};
 
 use {PID, UID, GID};
 use pidfile::read_pidfile;
+use system::{boot_time, cpu_count, read_connections, Connection, ConnectionKind};
 use utils::read_file;
 
 lazy_static! {
-    static ref TICKS_PER_SECOND: f64 = {
+    /// Number of clock ticks per second, used to convert CPU times to seconds.
+    pub static ref TICKS_PER_SECOND: f64 = {
         unsafe { sysconf(_SC_CLK_TCK) as f64 }
     };
     static ref PAGE_SIZE: u64 = {
@@ -41,6 +50,38 @@ fn parse_error(message: &str, path: &PathBuf) -> Error {
                format!("{} (from {})", message, path))
 }
 
+/// Return the last OS error, mapping `ESRCH` to a `NotFound` error.
+fn last_os_error(pid: PID) -> Error {
+    let error = Error::last_os_error();
+    match error.raw_os_error() {
+        Some(libc::ESRCH) => Error::new(ErrorKind::NotFound,
+                                        format!("Process {} does not exist", pid)),
+        _ => error,
+    }
+}
+
+/// Return the exit code from a status returned by `waitpid(2)`, or the negated signal number if
+/// the process was killed by a signal.
+fn decode_wait_status(status: i32) -> Option<i32> {
+    if libc::WIFEXITED(status) {
+        Some(libc::WEXITSTATUS(status))
+    } else if libc::WIFSIGNALED(status) {
+        Some(-libc::WTERMSIG(status))
+    } else {
+        None
+    }
+}
+
+/// Quote a string so that a POSIX shell would read it as a single word.
+fn shell_quote(s: &str) -> String {
+    let is_safe = |c: char| c.is_ascii_alphanumeric() || "@%+=:,./_-".contains(c);
+    if !s.is_empty() && s.chars().all(is_safe) {
+        s.to_string()
+    } else {
+        format!("'{}'", s.replace('\'', "'\"'\"'"))
+    }
+}
+
 /// Possible statuses for a process.
 #[derive(Clone,Copy,Debug)]
 pub enum State {
@@ -108,6 +149,290 @@ impl ToString for State {
     }
 }
 
+/// Scheduling policy of a process. See [sched(7)].
+///
+/// Policies added by newer kernels are represented as `Other`.
+///
+/// [sched(7)]: http://man7.org/linux/man-pages/man7/sched.7.html
+#[derive(Clone,Copy,Debug,PartialEq,Eq,Hash)]
+pub enum SchedPolicy {
+    Normal,
+    Fifo,
+    RR,
+    Batch,
+    Idle,
+    Deadline,
+    Other(u32),
+}
+
+impl SchedPolicy {
+    /// Returns a SchedPolicy based on a `SCHED_*` constant.
+    pub fn from_raw(policy: u32) -> Self {
+        match policy {
+            0 => SchedPolicy::Normal,
+            1 => SchedPolicy::Fifo,
+            2 => SchedPolicy::RR,
+            3 => SchedPolicy::Batch,
+            5 => SchedPolicy::Idle,
+            6 => SchedPolicy::Deadline,
+            _ => SchedPolicy::Other(policy),
+        }
+    }
+
+    /// Returns `true` for the realtime policies.
+    pub fn is_realtime(&self) -> bool {
+        matches!(*self, SchedPolicy::Fifo | SchedPolicy::RR | SchedPolicy::Deadline)
+    }
+}
+
+impl FromStr for SchedPolicy {
+    type Err = ParseIntError;
+
+    fn from_str(s: &str) -> ::std::result::Result<Self, ParseIntError> {
+        u32::from_str(s).map(SchedPolicy::from_raw)
+    }
+}
+
+/// Kernel flags of a process, decoded from `/proc/[pid]/stat`.
+///
+/// The `PF_*` flags are defined in [sched.h].
+///
+/// [sched.h]: https://github.com/torvalds/linux/blob/master/include/linux/sched.h
+#[derive(Clone,Copy,Debug,Default,PartialEq,Eq)]
+pub struct ProcessFlags(pub u32);
+
+impl ProcessFlags {
+    /// The process is an idle thread.
+    pub const IDLE: u32 = 0x0000_0002;
+    /// The process is exiting.
+    pub const EXITING: u32 = 0x0000_0004;
+    /// The process is a workqueue worker.
+    pub const WQ_WORKER: u32 = 0x0000_0020;
+    /// The process forked but didn't exec.
+    pub const FORKNOEXEC: u32 = 0x0000_0040;
+    /// The process used superuser privileges.
+    pub const SUPERPRIV: u32 = 0x0000_0100;
+    /// The process dumped core.
+    pub const DUMPCORE: u32 = 0x0000_0200;
+    /// The process was killed by a signal.
+    pub const SIGNALED: u32 = 0x0000_0400;
+    /// The process is allocating memory to free memory.
+    pub const MEMALLOC: u32 = 0x0000_0800;
+    /// The process is not frozen during suspend.
+    pub const NOFREEZE: u32 = 0x0000_8000;
+    /// The process is the kswapd daemon.
+    pub const KSWAPD: u32 = 0x0002_0000;
+    /// The process is a kernel thread.
+    pub const KTHREAD: u32 = 0x0020_0000;
+    /// The process has a randomized virtual address space.
+    pub const RANDOMIZE: u32 = 0x0040_0000;
+
+    /// Return `true` if all bits of `flag` are set.
+    pub fn contains(&self, flag: u32) -> bool {
+        self.0 & flag == flag
+    }
+}
+
+impl FromStr for ProcessFlags {
+    type Err = ParseIntError;
+
+    fn from_str(s: &str) -> ::std::result::Result<Self, ParseIntError> {
+        u32::from_str(s).map(ProcessFlags)
+    }
+}
+
+/// Signals that can be sent to a process.
+///
+/// Each variant has the value of the corresponding signal number, so `Signal::Term as i32` is
+/// `SIGTERM`. See [signal(7)].
+///
+/// [signal(7)]: http://man7.org/linux/man-pages/man7/signal.7.html
+#[derive(Clone,Copy,Debug,PartialEq,Eq,Hash)]
+#[repr(i32)]
+pub enum Signal {
+    Hup = libc::SIGHUP,
+    Int = libc::SIGINT,
+    Quit = libc::SIGQUIT,
+    Ill = libc::SIGILL,
+    Trap = libc::SIGTRAP,
+    Abrt = libc::SIGABRT,
+    Bus = libc::SIGBUS,
+    Fpe = libc::SIGFPE,
+    Kill = libc::SIGKILL,
+    Usr1 = libc::SIGUSR1,
+    Segv = libc::SIGSEGV,
+    Usr2 = libc::SIGUSR2,
+    Pipe = libc::SIGPIPE,
+    Alrm = libc::SIGALRM,
+    Term = libc::SIGTERM,
+    Chld = libc::SIGCHLD,
+    Cont = libc::SIGCONT,
+    Stop = libc::SIGSTOP,
+    Tstp = libc::SIGTSTP,
+    Ttin = libc::SIGTTIN,
+    Ttou = libc::SIGTTOU,
+    Urg = libc::SIGURG,
+    Xcpu = libc::SIGXCPU,
+    Xfsz = libc::SIGXFSZ,
+    Vtalrm = libc::SIGVTALRM,
+    Prof = libc::SIGPROF,
+    Winch = libc::SIGWINCH,
+    Io = libc::SIGIO,
+    Pwr = libc::SIGPWR,
+    Sys = libc::SIGSYS,
+}
+
+impl Signal {
+    /// Returns the Signal with the given signal number.
+    pub fn from_raw(signal: i32) -> Result<Self> {
+        const SIGNALS: [Signal; 30] = [
+            Signal::Hup, Signal::Int, Signal::Quit, Signal::Ill, Signal::Trap, Signal::Abrt,
+            Signal::Bus, Signal::Fpe, Signal::Kill, Signal::Usr1, Signal::Segv, Signal::Usr2,
+            Signal::Pipe, Signal::Alrm, Signal::Term, Signal::Chld, Signal::Cont, Signal::Stop,
+            Signal::Tstp, Signal::Ttin, Signal::Ttou, Signal::Urg, Signal::Xcpu, Signal::Xfsz,
+            Signal::Vtalrm, Signal::Prof, Signal::Winch, Signal::Io, Signal::Pwr, Signal::Sys,
+        ];
+
+        SIGNALS.iter()
+            .find(|s| **s as i32 == signal)
+            .cloned()
+            .ok_or_else(|| Error::new(ErrorKind::InvalidInput,
+                                      format!("Invalid signal number: {}", signal)))
+    }
+}
+
+/// I/O scheduling class of a process. See [ioprio_set(2)].
+///
+/// [ioprio_set(2)]: http://man7.org/linux/man-pages/man2/ioprio_set.2.html
+#[derive(Clone,Copy,Debug,PartialEq,Eq,Hash)]
+pub enum IoClass {
+    /// No class has been set, so the priority is derived from the nice value.
+    None,
+    RealTime,
+    BestEffort,
+    Idle,
+}
+
+/// I/O scheduling class and priority of a process.
+///
+/// The value ranges from 0 (highest priority) to 7 for the `RealTime` and `BestEffort` classes,
+/// and is always 0 for the `None` and `Idle` classes.
+#[derive(Clone,Copy,Debug,PartialEq,Eq,Hash)]
+pub struct IoNice {
+    pub class: IoClass,
+    pub value: u8,
+}
+
+const IOPRIO_WHO_PROCESS: libc::c_long = 1;
+const IOPRIO_CLASS_SHIFT: u32 = 13;
+/// Newer kernels store hints between the class and the level, so only the low bits are the level.
+const IOPRIO_LEVEL_MASK: i32 = 0x7;
+
+impl IoNice {
+    fn from_raw(ioprio: i32) -> Result<IoNice> {
+        let class = match ioprio >> IOPRIO_CLASS_SHIFT {
+            0 => IoClass::None,
+            1 => IoClass::RealTime,
+            2 => IoClass::BestEffort,
+            3 => IoClass::Idle,
+            class => return Err(Error::new(ErrorKind::InvalidData,
+                                           format!("Unknown I/O class {}", class))),
+        };
+
+        Ok(IoNice {
+            class,
+            value: (ioprio & IOPRIO_LEVEL_MASK) as u8,
+        })
+    }
+
+    fn to_raw(self) -> Result<i32> {
+        let class = match self.class {
+            IoClass::None => 0,
+            IoClass::RealTime => 1,
+            IoClass::BestEffort => 2,
+            IoClass::Idle => 3,
+        };
+        let valid = match self.class {
+            IoClass::None | IoClass::Idle => self.value == 0,
+            IoClass::RealTime | IoClass::BestEffort => self.value <= 7,
+        };
+        if !valid {
+            return Err(Error::new(ErrorKind::InvalidInput,
+                                  format!("Invalid value {} for I/O class {:?}",
+                                          self.value, self.class)));
+        }
+
+        Ok(class << IOPRIO_CLASS_SHIFT | i32::from(self.value))
+    }
+}
+
+/// A resource limited by `setrlimit(2)`. See [getrlimit(2)].
+///
+/// [getrlimit(2)]: http://man7.org/linux/man-pages/man2/getrlimit.2.html
+#[derive(Clone,Copy,Debug,PartialEq,Eq,Hash)]
+pub enum Resource {
+    As,
+    Core,
+    Cpu,
+    Data,
+    Fsize,
+    Locks,
+    Memlock,
+    Msgqueue,
+    Nice,
+    Nofile,
+    Nproc,
+    Rss,
+    Rtprio,
+    Rttime,
+    Sigpending,
+    Stack,
+}
+
+impl Resource {
+    /// The `RLIMIT_*` constants have a different type in each C library, so use the `int` that
+    /// `prlimit(2)` is declared with.
+    fn to_raw(self) -> libc::c_int {
+        match self {
+            Resource::As => libc::RLIMIT_AS as libc::c_int,
+            Resource::Core => libc::RLIMIT_CORE as libc::c_int,
+            Resource::Cpu => libc::RLIMIT_CPU as libc::c_int,
+            Resource::Data => libc::RLIMIT_DATA as libc::c_int,
+            Resource::Fsize => libc::RLIMIT_FSIZE as libc::c_int,
+            Resource::Locks => libc::RLIMIT_LOCKS as libc::c_int,
+            Resource::Memlock => libc::RLIMIT_MEMLOCK as libc::c_int,
+            Resource::Msgqueue => libc::RLIMIT_MSGQUEUE as libc::c_int,
+            Resource::Nice => libc::RLIMIT_NICE as libc::c_int,
+            Resource::Nofile => libc::RLIMIT_NOFILE as libc::c_int,
+            Resource::Nproc => libc::RLIMIT_NPROC as libc::c_int,
+            Resource::Rss => libc::RLIMIT_RSS as libc::c_int,
+            Resource::Rtprio => libc::RLIMIT_RTPRIO as libc::c_int,
+            Resource::Rttime => libc::RLIMIT_RTTIME as libc::c_int,
+            Resource::Sigpending => libc::RLIMIT_SIGPENDING as libc::c_int,
+            Resource::Stack => libc::RLIMIT_STACK as libc::c_int,
+        }
+    }
+}
+
+/// Soft and hard limits for a resource, where `None` means unlimited.
+#[derive(Clone,Copy,Debug,PartialEq,Eq,Hash)]
+pub struct RLimit {
+    pub soft: Option<u64>,
+    pub hard: Option<u64>,
+}
+
+fn rlim_from_raw(rlim: libc::rlim_t) -> Option<u64> {
+    if rlim == libc::RLIM_INFINITY {
+        None
+    } else {
+        Some(rlim)
+    }
+}
+
+fn rlim_to_raw(limit: Option<u64>) -> libc::rlim_t {
+    limit.unwrap_or(libc::RLIM_INFINITY)
+}
+
 /// Memory usage of a process read from `/proc/[pid]/statm`.
 ///
 /// The `lib` [4, u64] and `dt` [6, u64] fields are ignored.
@@ -150,200 +475,1052 @@ impl Memory {
     }
 }
 
-pub struct Fd {
-    /// Number of fd
-    pub number: i32,
+/// Detailed memory usage of a process read from `/proc/[pid]/smaps_rollup`.
+///
+/// Unlike `Memory`, this can tell how much memory is unique to the process and how much is shared
+/// with others. All fields are in bytes.
+#[derive(Clone,Copy,Debug,Default)]
+pub struct MemoryFullInfo {
+    /// Resident Set Size.
+    pub rss: u64,
 
-    /// Path to resolved fd
-    pub path: PathBuf,
+    /// Proportional Set Size, where each shared page is divided by the number of processes
+    /// sharing it.
+    pub pss: u64,
 
-}
+    /// Unique Set Size, the memory that would be freed if the process exited.
+    pub uss: u64,
 
+    /// Memory swapped out to disk.
+    pub swap: u64,
 
-/// Information about a process gathered from `/proc/[pid]/stat`.
-///
-/// More information about specific fields can be found in [proc(5)].
-///
-/// # Field sizes
-///
-/// The manual pages for `proc` define integer sizes using `scanf(3)` format specifiers, which parse
-/// to implementation specific sizes. This is obviously a terrible idea, and so this code makes some
-/// assumptions about the sizes of those specifiers.
-///
-/// These assumptions are backed up by `libc::types::os::arch::posix88::pid_t`, which declares PIDs
-/// to be signed 32 bit integers. `proc(5)` declares that PIDs use the `%d` format specifier.
-///
-/// - `%d` / `%u` - 32 bit signed and unsigned integers
-/// - `%ld` / `%lu` - 64 bit signed and unsigned integers
-///
-/// Rust currently has no support for 128 bit integers ([rfc521]) so `%llu` (used by the `starttime`
-/// and `delayacct_blkio_ticks` fields) is is instead represented by a 64 bit integer, with the hope
-/// that doesn't break.
-///
-/// ### CPU time fields and clock ticks
-///
-/// The CPU time fields are very strange. Inside the Linux kernel they each use the same type
-/// ([array.c:361]) but when printed use different types ([array.c:456]) - the fields `utime`,
-/// `stime` and `gtime` are unsigned integers, whereas `cutime`, `cstime` and `cgtime` are signed
-/// integers.
-///
-/// These values are all returned as a number of clock ticks, which can be divided by
-/// `sysconf(_SC_CLK_TCK)` to get a value in seconds. The `Process` struct does this conversion
-/// automatically, and all CPU time fields use the `f64` type. A corresponding `_ticks` field  (e.g.
-/// `utime_ticks` gives the raw number of ticks as found in `/proc`.
-///
-/// # Unmaintained fields
-///
-/// The `itrealvalue` [20], `nswap` [35] and `cnswap` [36] fields are not maintained, and in some
-/// cases are hardcoded to 0 in the kernel. The `signal` [30], `blocked` [31], `sigignore` [32] and
-/// `sigcatch` [33] fields are included as private fields, but `proc(5)` recommends the use of
-/// `/proc/[pid]/status` instead.
-///
-/// # Examples
-///
-/// ```
-/// psutil::process::Process::new(psutil::getpid()).unwrap();
-/// ```
-///
-/// [array.c:361]: https://github.com/torvalds/linux/blob/master/fs/proc/array.c#L361
-/// [array.c:456]: https://github.com/torvalds/linux/blob/master/fs/proc/array.c#L456
-/// [proc(5)]: http://man7.org/linux/man-pages/man5/proc.5.html
-/// [rfc521]: https://github.com/rust-lang/rfcs/issues/521
-#[derive(Clone,Debug)]
-pub struct Process {
-    /// PID of the process.
-    pub pid: PID,
+    /// Shared memory that has not been modified.
+    pub shared_clean: u64,
 
-    /// UID of the process.
-    pub uid: UID,
+    /// Shared memory that has been modified.
+    pub shared_dirty: u64,
 
-    /// UID of the process.
-    pub gid: GID,
+    /// Private memory that has not been modified.
+    pub private_clean: u64,
 
-    /// Filename of the executable.
-    pub comm: String,
+    /// Private memory that has been modified.
+    pub private_dirty: u64,
+}
 
-    /// State of the process as an enum.
-    pub state: State,
+impl MemoryFullInfo {
+    /// Reads `/proc/[pid]/smaps_rollup`, or sums `/proc/[pid]/smaps` on kernels before 4.14.
+    pub fn new(pid: PID) -> Result<MemoryFullInfo> {
+        let path = procfs_path(pid, "smaps_rollup");
+        match fs::read(&path) {
+            Ok(smaps) => MemoryFullInfo::parse(&smaps, &path),
+            Err(ref e) if e.kind() == ErrorKind::NotFound => {
+                let path = procfs_path(pid, "smaps");
+                MemoryFullInfo::parse(&fs::read(&path)?, &path)
+            }
+            Err(e) => Err(e),
+        }
+    }
 
-    /// PID of the parent process.
-    pub ppid: PID,
+    /// Sums the fields of each mapping in `smaps`, which has the same format as `smaps_rollup`.
+    fn parse(smaps: &[u8], path: &PathBuf) -> Result<MemoryFullInfo> {
+        let mut totals: HashMap<&str, u64> = HashMap::new();
+        for line in smaps.split(|&b| b == b'\n') {
+            if let Some((key, bytes)) = parse_smaps_line(line, path)? {
+                *totals.entry(key).or_insert(0) += bytes;
+            }
+        }
 
-    /// Process group ID.
-    pub pgrp: i32,
+        let get = |key: &str| totals.get(key).cloned().unwrap_or(0);
+        Ok(MemoryFullInfo {
+            rss: get("Rss"),
+            pss: get("Pss"),
+            uss: get("Private_Clean") + get("Private_Dirty"),
+            swap: get("Swap"),
+            shared_clean: get("Shared_Clean"),
+            shared_dirty: get("Shared_Dirty"),
+            private_clean: get("Private_Clean"),
+            private_dirty: get("Private_Dirty"),
+        })
+    }
+}
 
-    /// Session ID.
-    pub session: i32,
+/// Parse a line such as `Rss:  1304 kB` from `smaps`, returning the key and a number of bytes.
+///
+/// Returns `None` for mapping headers and fields that are not sizes (e.g. `VmFlags`).
+fn parse_smaps_line<'a>(line: &'a [u8], path: &PathBuf) -> Result<Option<(&'a str, u64)>> {
+    // Only the path in a mapping header can contain bytes that aren't UTF-8
+    let line = match ::std::str::from_utf8(line) {
+        Ok(line) => line,
+        Err(_) => return Ok(None),
+    };
+    let fields: Vec<&str> = line.split_whitespace().collect();
+    if fields.len() != 3 || fields[2] != "kB" || !fields[0].ends_with(':') {
+        return Ok(None);
+    }
 
-    /// Controlling terminal of the process [TODO: Actually two numbers].
-    pub tty_nr: i32,
+    let kb = u64::from_str(fields[1])
+        .map_err(|e| parse_error(&format!("Could not parse {}: {}", fields[0], e), path))?;
+    Ok(Some((&fields[0][..fields[0].len() - 1], kb * 1024)))
+}
 
-    /// ID of the foreground group of the controlling terminal.
-    pub tpgid: i32,
+/// Location of a memory mapping, read from `/proc/[pid]/maps`.
+#[derive(Clone,Debug,PartialEq,Eq)]
+pub struct MapRegion {
+    /// Start address of the mapping.
+    pub start: u64,
 
-    /// Kernel flags for the process.
-    pub flags: u32,
+    /// End address of the mapping.
+    pub end: u64,
 
-    /// Minor faults.
-    pub minflt: u64,
+    /// Permissions (e.g. `r-xp`).
+    pub perms: String,
 
-    /// Minor faults by child processes.
-    pub cminflt: u64,
+    /// Offset into the mapped file.
+    pub offset: u64,
 
-    /// Major faults.
-    pub majflt: u64,
+    /// Major number of the device containing the mapped file.
+    pub dev_major: u32,
 
-    /// Major faults by child processes.
-    pub cmajflt: u64,
+    /// Minor number of the device containing the mapped file.
+    pub dev_minor: u32,
 
-    /// Time scheduled in user mode (seconds).
-    pub utime: f64,
+    /// Inode of the mapped file, or 0.
+    pub inode: u64,
+}
 
-    /// Time scheduled in user mode (ticks).
-    pub utime_ticks: u64,
+/// A memory mapping of a process and its memory usage, read from `/proc/[pid]/smaps`.
+///
+/// All sizes are in bytes.
+#[derive(Clone,Debug)]
+pub struct MemoryMap {
+    /// Path of the mapped file, a pseudo-path such as `[heap]` or `[stack]`, or `[anon]` for
+    /// anonymous mappings.
+    pub path: PathBuf,
 
-    /// Time scheduled in kernel mode (seconds).
-    pub stime: f64,
+    /// Location of the mapping, or `None` if mappings have been grouped by path.
+    pub region: Option<MapRegion>,
 
-    /// Time scheduled in kernel mode (ticks).
-    pub stime_ticks: u64,
+    /// Size of the mapping.
+    pub size: u64,
 
-    /// Time waited-for child processes were scheduled in user mode (seconds).
-    pub cutime: f64,
+    /// Resident Set Size.
+    pub rss: u64,
 
-    /// Time waited-for child processes were scheduled in user mode (ticks).
-    pub cutime_ticks: i64,
+    /// Proportional Set Size.
+    pub pss: u64,
 
-    /// Time waited-for child processes were scheduled in kernel mode (seconds).
-    pub cstime: f64,
+    /// Shared memory that has not been modified.
+    pub shared_clean: u64,
 
-    /// Time waited-for child processes were scheduled in kernel mode (ticks).
-    pub cstime_ticks: i64,
+    /// Shared memory that has been modified.
+    pub shared_dirty: u64,
 
-    /// Priority value (-100..-2 | 0..39).
-    pub priority: i64,
+    /// Private memory that has not been modified.
+    pub private_clean: u64,
 
-    /// Nice value (-20..19).
-    pub nice: i64,
+    /// Private memory that has been modified.
+    pub private_dirty: u64,
 
-    /// Number of threads in the process.
-    pub num_threads: i64,
+    /// Memory that has been accessed.
+    pub referenced: u64,
 
-    // Unmaintained field since linux 2.6.17, always 0.
-    // itrealvalue: i64,
-    /// Time the process was started after system boot (seconds).
-    pub starttime: f64,
+    /// Memory that does not belong to any file.
+    pub anonymous: u64,
 
-    /// Time the process was started after system boot (ticks).
-    pub starttime_ticks: u64,
+    /// Memory swapped out to disk.
+    pub swap: u64,
+}
 
-    /// Virtual memory size in bytes.
-    pub vsize: u64,
+impl MemoryMap {
+    fn new(path: PathBuf, region: Option<MapRegion>) -> MemoryMap {
+        MemoryMap {
+            path,
+            region,
+            size: 0,
+            rss: 0,
+            pss: 0,
+            shared_clean: 0,
+            shared_dirty: 0,
+            private_clean: 0,
+            private_dirty: 0,
+            referenced: 0,
+            anonymous: 0,
+            swap: 0,
+        }
+    }
 
-    /// Resident Set Size (bytes).
-    pub rss: i64,
+    /// Parse each mapping in `/proc/[pid]/smaps`.
+    fn parse(smaps: &[u8], path: &PathBuf) -> Result<Vec<MemoryMap>> {
+        let mut maps: Vec<MemoryMap> = Vec::new();
+
+        for line in smaps.split(|&b| b == b'\n') {
+            let first = match line.split(u8::is_ascii_whitespace).find(|f| !f.is_empty()) {
+                Some(first) => first,
+                None => continue,
+            };
+            if !first.ends_with(b":") {
+                let map = MemoryMap::parse_header(line).ok_or_else(|| {
+                    let line = String::from_utf8_lossy(line);
+                    parse_error(&format!("Could not parse mapping {:?}", line), path)
+                })?;
+                maps.push(map);
+                continue;
+            }
 
-    /// Current soft limit on process RSS (bytes).
-    pub rsslim: u64,
+            let map = match maps.last_mut() {
+                Some(map) => map,
+                None => return Err(parse_error("Expected a mapping before its fields", path)),
+            };
+            if let Some((key, bytes)) = parse_smaps_line(line, path)? {
+                map.add_field(key, bytes);
+            }
+        }
 
-    // These values are memory addresses
-    startcode: u64,
-    endcode: u64,
-    startstack: u64,
-    kstkesp: u64,
-    kstkeip: u64,
+        Ok(maps)
+    }
 
-    // Signal bitmaps.
-    // These are obsolete, use `/proc/[pid]/status` instead.
-    signal: u64,
-    blocked: u64,
-    sigignore: u64,
-    sigcatch: u64,
+    /// Parse a mapping header such as `00400000-00452000 r-xp 00000000 08:02 173521  /usr/bin/foo`.
+    ///
+    /// The path is kept as raw bytes, since file names need not be valid UTF-8.
+    fn parse_header(line: &[u8]) -> Option<MemoryMap> {
+        let mut fields = line.splitn(6, |&b| b == b' ');
+        let mut field = || fields.next().and_then(|field| ::std::str::from_utf8(field).ok());
+        let mut range = field()?.splitn(2, '-');
+        let start = u64::from_str_radix(range.next()?, 16).ok()?;
+        let end = u64::from_str_radix(range.next()?, 16).ok()?;
+        let perms = field()?.to_string();
+        let offset = u64::from_str_radix(field()?, 16).ok()?;
+        let mut dev = field()?.splitn(2, ':');
+        let dev_major = u32::from_str_radix(dev.next()?, 16).ok()?;
+        let dev_minor = u32::from_str_radix(dev.next()?, 16).ok()?;
+        let inode = field()?.parse().ok()?;
+        // The path is padded with spaces to line up
+        let path = fields.next().map(|path| {
+            let start = path.iter().position(|&b| b != b' ').unwrap_or(path.len());
+            &path[start..]
+        });
+        let path = match path {
+            Some(path) if !path.is_empty() => PathBuf::from(OsStr::from_bytes(path)),
+            _ => PathBuf::from("[anon]"),
+        };
 
-    /// Channel the process is waiting on (address of a system call).
-    pub wchan: u64,
+        let region = MapRegion { start, end, perms, offset, dev_major, dev_minor, inode };
+        Some(MemoryMap::new(path, Some(region)))
+    }
 
-    // Number of pages swapped (not maintained).
-    // pub nswap: u64,
-    //
-    // Number of pages swapped for child processes (not maintained).
-    // pub cnswap: u64,
-    /// Signal sent to parent when process dies.
-    pub exit_signal: i32,
+    fn add_field(&mut self, key: &str, bytes: u64) {
+        match key {
+            "Size" => self.size += bytes,
+            "Rss" => self.rss += bytes,
+            "Pss" => self.pss += bytes,
+            "Shared_Clean" => self.shared_clean += bytes,
+            "Shared_Dirty" => self.shared_dirty += bytes,
+            "Private_Clean" => self.private_clean += bytes,
+            "Private_Dirty" => self.private_dirty += bytes,
+            "Referenced" => self.referenced += bytes,
+            "Anonymous" => self.anonymous += bytes,
+            "Swap" => self.swap += bytes,
+            _ => {}
+        }
+    }
 
-    /// Number of the CPU the process was last executed on.
-    pub processor: i32,
+    /// Sum mappings with the same path, keeping the order each path first appeared in.
+    fn group(maps: Vec<MemoryMap>) -> Vec<MemoryMap> {
+        let mut grouped: Vec<MemoryMap> = Vec::new();
+        let mut index: HashMap<PathBuf, usize> = HashMap::new();
+
+        for map in maps {
+            let i = *index.entry(map.path.clone()).or_insert_with(|| {
+                grouped.push(MemoryMap::new(map.path.clone(), None));
+                grouped.len() - 1
+            });
+            let group = &mut grouped[i];
+            group.size += map.size;
+            group.rss += map.rss;
+            group.pss += map.pss;
+            group.shared_clean += map.shared_clean;
+            group.shared_dirty += map.shared_dirty;
+            group.private_clean += map.private_clean;
+            group.private_dirty += map.private_dirty;
+            group.referenced += map.referenced;
+            group.anonymous += map.anonymous;
+            group.swap += map.swap;
+        }
 
-    /// Real-time scheduling priority (0 | 1..99).
-    pub rt_priority: u32,
+        grouped
+    }
+}
 
-    /// Scheduling policy.
-    pub policy: u32,
+/// I/O statistics of a process read from `/proc/[pid]/io`.
+#[derive(Clone,Copy,Debug,Default)]
+pub struct IoCounters {
+    /// Bytes read by `read(2)` and similar system calls, including from the page cache.
+    pub rchar: u64,
 
-    /// Aggregated block I/O delays (seconds).
-    pub delayacct_blkio: f64,
+    /// Bytes written by `write(2)` and similar system calls, including to the page cache.
+    pub wchar: u64,
 
-    /// Aggregated block I/O delays (ticks).
+    /// Number of read system calls.
+    pub syscr: u64,
+
+    /// Number of write system calls.
+    pub syscw: u64,
+
+    /// Bytes fetched from the storage layer.
+    pub read_bytes: u64,
+
+    /// Bytes sent to the storage layer.
+    pub write_bytes: u64,
+
+    /// Bytes the process caused to not be written, by truncating dirty page cache.
+    pub cancelled_write_bytes: u64,
+}
+
+impl IoCounters {
+    /// Reads `/proc/[pid]/io`.
+    ///
+    /// Reading the file requires the same permissions as tracing the process, so this returns a
+    /// `PermissionDenied` error for other users' processes.
+    pub fn new(pid: PID) -> Result<IoCounters> {
+        let path = procfs_path(pid, "io");
+        let io = read_file(&path)?;
+        IoCounters::parse(&io, &path)
+    }
+
+    fn parse(io: &str, path: &PathBuf) -> Result<IoCounters> {
+        let mut map = HashMap::new();
+        for line in io.lines() {
+            let mut fields = line.splitn(2, ':');
+            if let (Some(key), Some(value)) = (fields.next(), fields.next()) {
+                let value = u64::from_str(value.trim())
+                    .map_err(|e| parse_error(&format!("Could not parse {}: {}", key, e), path))?;
+                map.insert(key, value);
+            }
+        }
+
+        let get = |key: &str| -> Result<u64> {
+            map.get(key).cloned().ok_or_else(|| parse_error(&format!("{} not found", key), path))
+        };
+        Ok(IoCounters {
+            rchar: get("rchar")?,
+            wchar: get("wchar")?,
+            syscr: get("syscr")?,
+            syscw: get("syscw")?,
+            read_bytes: get("read_bytes")?,
+            write_bytes: get("write_bytes")?,
+            cancelled_write_bytes: get("cancelled_write_bytes")?,
+        })
+    }
+}
+
+/// Real, effective, saved set and filesystem user IDs of a process.
+#[derive(Clone,Copy,Debug,PartialEq,Eq)]
+pub struct Uids {
+    pub real: UID,
+    pub effective: UID,
+    pub saved: UID,
+    pub filesystem: UID,
+}
+
+/// Real, effective, saved set and filesystem group IDs of a process.
+#[derive(Clone,Copy,Debug,PartialEq,Eq)]
+pub struct Gids {
+    pub real: GID,
+    pub effective: GID,
+    pub saved: GID,
+    pub filesystem: GID,
+}
+
+/// A set of signals, decoded from a mask in `/proc/[pid]/status`.
+///
+/// Bit `n - 1` of the mask is set if signal `n` is in the set.
+#[derive(Clone,Copy,Debug,Default,PartialEq,Eq)]
+pub struct SignalSet(pub u64);
+
+impl SignalSet {
+    /// Return `true` if the set contains the signal number `signal`.
+    pub fn contains(&self, signal: i32) -> bool {
+        signal > 0 && signal <= 64 && self.0 & (1 << (signal - 1)) != 0
+    }
+
+    /// Return the signal numbers in the set, in ascending order.
+    pub fn signals(&self) -> Vec<i32> {
+        (1..65).filter(|&signal| self.contains(signal)).collect()
+    }
+}
+
+/// A set of capabilities, decoded from a mask in `/proc/[pid]/status`.
+///
+/// Bit `n` of the mask is set if capability `n` (e.g. 21 for `CAP_SYS_ADMIN`) is in the set. See
+/// [capabilities(7)].
+///
+/// [capabilities(7)]: http://man7.org/linux/man-pages/man7/capabilities.7.html
+#[derive(Clone,Copy,Debug,Default,PartialEq,Eq)]
+pub struct CapabilitySet(pub u64);
+
+impl CapabilitySet {
+    /// Return `true` if the set contains the capability number `capability`.
+    pub fn contains(&self, capability: u32) -> bool {
+        capability < 64 && self.0 & (1 << capability) != 0
+    }
+
+    /// Return the capability numbers in the set, in ascending order.
+    pub fn capabilities(&self) -> Vec<u32> {
+        (0..64).filter(|&capability| self.contains(capability)).collect()
+    }
+}
+
+/// Seccomp mode of a process.
+#[derive(Clone,Copy,Debug,PartialEq,Eq)]
+pub enum SeccompMode {
+    Disabled,
+    Strict,
+    Filter,
+}
+
+/// Information about a process gathered from `/proc/[pid]/status`.
+///
+/// Fields that were added in later kernel versions, or that are not present for kernel threads,
+/// are `Option`s. Memory fields are in bytes.
+#[derive(Clone,Debug)]
+pub struct ProcessStatus {
+    /// Filename of the executable.
+    pub name: String,
+
+    /// File mode creation mask (since Linux 4.7).
+    pub umask: Option<u32>,
+
+    /// State of the process.
+    pub state: State,
+
+    /// Thread group ID (the PID of the process).
+    pub tgid: PID,
+
+    /// Thread ID.
+    pub pid: PID,
+
+    /// PID of the parent process.
+    pub ppid: PID,
+
+    /// PID of the process tracing this process, or 0.
+    pub tracer_pid: PID,
+
+    /// User IDs of the process.
+    pub uids: Uids,
+
+    /// Group IDs of the process.
+    pub gids: Gids,
+
+    /// Supplementary group IDs.
+    pub groups: Vec<GID>,
+
+    /// Peak virtual memory size.
+    pub vm_peak: Option<u64>,
+
+    /// Virtual memory size.
+    pub vm_size: Option<u64>,
+
+    /// Peak resident set size ("high water mark").
+    pub vm_hwm: Option<u64>,
+
+    /// Resident set size.
+    pub vm_rss: Option<u64>,
+
+    /// Swapped-out virtual memory size.
+    pub vm_swap: Option<u64>,
+
+    /// Number of threads in the process.
+    pub threads: u64,
+
+    /// Signals pending for the thread.
+    pub sig_pnd: SignalSet,
+
+    /// Signals pending for the process as a whole.
+    pub shd_pnd: SignalSet,
+
+    /// Signals being blocked.
+    pub sig_blk: SignalSet,
+
+    /// Signals being ignored.
+    pub sig_ign: SignalSet,
+
+    /// Signals being caught.
+    pub sig_cgt: SignalSet,
+
+    /// Inheritable capabilities.
+    pub cap_inh: CapabilitySet,
+
+    /// Permitted capabilities.
+    pub cap_prm: CapabilitySet,
+
+    /// Effective capabilities.
+    pub cap_eff: CapabilitySet,
+
+    /// Capability bounding set.
+    pub cap_bnd: CapabilitySet,
+
+    /// Ambient capabilities (since Linux 4.3).
+    pub cap_amb: Option<CapabilitySet>,
+
+    /// Value of the `no_new_privs` bit (since Linux 4.10).
+    pub no_new_privs: Option<bool>,
+
+    /// Seccomp mode of the process (since Linux 3.8).
+    pub seccomp: Option<SeccompMode>,
+
+    /// CPUs the process may run on.
+    pub cpus_allowed_list: Vec<u32>,
+
+    /// Number of voluntary context switches.
+    pub voluntary_ctxt_switches: u64,
+
+    /// Number of involuntary context switches.
+    pub nonvoluntary_ctxt_switches: u64,
+}
+
+impl ProcessStatus {
+    /// Read and parse `/proc/[pid]/status`.
+    pub fn new(pid: PID) -> Result<ProcessStatus> {
+        let path = procfs_path(pid, "status");
+        let status = read_file(&path)?;
+        ProcessStatus::parse(&status, &path)
+    }
+
+    /// Parse only the real UID and GID, so that reading a process doesn't depend on the other
+    /// fields, some of which are missing from sandboxed `/proc` implementations.
+    fn parse_real_ids(status: &str, path: &PathBuf) -> Result<(UID, GID)> {
+        let real_id = |key: &str| -> Result<u32> {
+            let value = status.lines()
+                .find_map(|line| line.strip_prefix(key))
+                .and_then(|ids| ids.split_whitespace().next())
+                .ok_or_else(|| parse_error(&format!("{} not found", key), path))?;
+            Ok(try_parse!(value))
+        };
+
+        Ok((real_id("Uid:")?, real_id("Gid:")?))
+    }
+
+    fn parse(status: &str, path: &PathBuf) -> Result<ProcessStatus> {
+        let mut map = HashMap::new();
+        for line in status.lines() {
+            if let Some(colon) = line.find(':') {
+                map.insert(&line[..colon], line[colon + 1..].trim());
+            }
+        }
+
+        let get = |key: &str| -> Result<&str> {
+            map.get(key).cloned().ok_or_else(|| parse_error(&format!("{} not found", key), path))
+        };
+        let number = |key: &str| -> Result<u64> {
+            let value = get(key)?;
+            Ok(try_parse!(value))
+        };
+        let bytes = |key: &str| -> Result<Option<u64>> {
+            match map.get(key) {
+                Some(value) => {
+                    let kb = try_parse!(value.trim_end_matches(" kB").trim(), u64::from_str);
+                    Ok(Some(kb * 1024))
+                }
+                None => Ok(None),
+            }
+        };
+        let mask = |key: &str| -> Result<u64> {
+            let value = get(key)?;
+            Ok(try_parse!(value, ProcessStatus::parse_hex))
+        };
+        let ids = |key: &str| -> Result<Vec<u32>> {
+            let mut ids = Vec::new();
+            for id in get(key)?.split_whitespace() {
+                ids.push(try_parse!(id));
+            }
+            if ids.len() != 4 {
+                return Err(parse_error(&format!("Expected 4 IDs for {}", key), path));
+            }
+            Ok(ids)
+        };
+
+        let uids = ids("Uid")?;
+        let gids = ids("Gid")?;
+
+        Ok(ProcessStatus {
+            name: get("Name")?.to_string(),
+            umask: match map.get("Umask") {
+                Some(umask) => Some(try_parse!(umask, ProcessStatus::parse_octal)),
+                None => None,
+            },
+            state: State::from_char(get("State")?.chars().next().unwrap_or(' '))?,
+            tgid: try_parse!(get("Tgid")?),
+            pid: try_parse!(get("Pid")?),
+            ppid: try_parse!(get("PPid")?),
+            tracer_pid: try_parse!(get("TracerPid")?),
+            uids: Uids { real: uids[0], effective: uids[1], saved: uids[2], filesystem: uids[3] },
+            gids: Gids { real: gids[0], effective: gids[1], saved: gids[2], filesystem: gids[3] },
+            groups: {
+                let mut groups = Vec::new();
+                for group in get("Groups")?.split_whitespace() {
+                    groups.push(try_parse!(group));
+                }
+                groups
+            },
+            vm_peak: bytes("VmPeak")?,
+            vm_size: bytes("VmSize")?,
+            vm_hwm: bytes("VmHWM")?,
+            vm_rss: bytes("VmRSS")?,
+            vm_swap: bytes("VmSwap")?,
+            threads: number("Threads")?,
+            sig_pnd: SignalSet(mask("SigPnd")?),
+            shd_pnd: SignalSet(mask("ShdPnd")?),
+            sig_blk: SignalSet(mask("SigBlk")?),
+            sig_ign: SignalSet(mask("SigIgn")?),
+            sig_cgt: SignalSet(mask("SigCgt")?),
+            cap_inh: CapabilitySet(mask("CapInh")?),
+            cap_prm: CapabilitySet(mask("CapPrm")?),
+            cap_eff: CapabilitySet(mask("CapEff")?),
+            cap_bnd: CapabilitySet(mask("CapBnd")?),
+            cap_amb: match map.get("CapAmb") {
+                Some(_) => Some(CapabilitySet(mask("CapAmb")?)),
+                None => None,
+            },
+            no_new_privs: match map.get("NoNewPrivs") {
+                Some(_) => Some(number("NoNewPrivs")? != 0),
+                None => None,
+            },
+            seccomp: match map.get("Seccomp") {
+                Some(_) => match number("Seccomp")? {
+                    0 => Some(SeccompMode::Disabled),
+                    1 => Some(SeccompMode::Strict),
+                    2 => Some(SeccompMode::Filter),
+                    mode => {
+                        return Err(parse_error(&format!("Invalid seccomp mode {}", mode), path))
+                    }
+                },
+                None => None,
+            },
+            cpus_allowed_list: ProcessStatus::parse_list(get("Cpus_allowed_list")?)?,
+            voluntary_ctxt_switches: number("voluntary_ctxt_switches")?,
+            nonvoluntary_ctxt_switches: number("nonvoluntary_ctxt_switches")?,
+        })
+    }
+
+    fn parse_hex(value: &str) -> ::std::result::Result<u64, ParseIntError> {
+        u64::from_str_radix(value, 16)
+    }
+
+    fn parse_octal(value: &str) -> ::std::result::Result<u32, ParseIntError> {
+        u32::from_str_radix(value, 8)
+    }
+
+    /// Parse a list of ranges such as `0-3,8,10-11`.
+    fn parse_list(value: &str) -> Result<Vec<u32>> {
+        let invalid = || Error::new(ErrorKind::InvalidData,
+                                    format!("Invalid list of ranges {:?}", value));
+
+        let mut list = Vec::new();
+        for range in value.split(',').filter(|range| !range.is_empty()) {
+            let mut bounds = range.splitn(2, '-').map(u32::from_str);
+            let start = match bounds.next() {
+                Some(Ok(start)) => start,
+                _ => return Err(invalid()),
+            };
+            let end = match bounds.next() {
+                Some(Ok(end)) => end,
+                Some(Err(_)) => return Err(invalid()),
+                None => start,
+            };
+            if start > end {
+                return Err(invalid());
+            }
+            list.extend(start..=end);
+        }
+        Ok(list)
+    }
+}
+
+/// Environment of a process read from `/proc/[pid]/environ`, without any conversion to UTF-8.
+#[derive(Clone,Debug,Default,PartialEq,Eq)]
+pub struct Environ {
+    /// Variables in the order they appear, including any duplicate names.
+    pub vars: Vec<(OsString, OsString)>,
+
+    /// Entries that don't contain an `=`, which a process can create by modifying its own
+    /// environment.
+    pub malformed: Vec<OsString>,
+}
+
+impl Environ {
+    fn parse(environ: &[u8]) -> Environ {
+        let mut result = Environ::default();
+        for entry in environ.split(|&b| b == b'\0').filter(|entry| !entry.is_empty()) {
+            match entry.iter().position(|&b| b == b'=') {
+                Some(i) => result.vars.push((OsStr::from_bytes(&entry[..i]).to_os_string(),
+                                             OsStr::from_bytes(&entry[i + 1..]).to_os_string())),
+                None => result.malformed.push(OsStr::from_bytes(entry).to_os_string()),
+            }
+        }
+        result
+    }
+
+    /// Return the value of the last variable named `name`, as the C library would.
+    pub fn get<S: AsRef<OsStr>>(&self, name: S) -> Option<&OsStr> {
+        self.vars.iter()
+            .rev()
+            .find(|&(key, _)| key == name.as_ref())
+            .map(|(_, value)| value.as_os_str())
+    }
+}
+
+/// A terminal device, decoded from the `tty_nr` field of `/proc/[pid]/stat`.
+#[derive(Clone,Copy,Debug,PartialEq,Eq,Hash)]
+pub struct Terminal {
+    pub major: u32,
+    pub minor: u32,
+}
+
+impl Terminal {
+    /// Decode a `tty_nr` value, returning `None` if the process has no controlling terminal.
+    ///
+    /// The minor device number is contained in the combination of bits 31 to 20 and 7 to 0, and
+    /// the major device number is in bits 19 to 8, as encoded by the kernel's `new_encode_dev`.
+    /// [proc(5)] only mentions bits 15 to 8, which is enough for majors below 256.
+    ///
+    /// [proc(5)]: http://man7.org/linux/man-pages/man5/proc.5.html
+    pub fn from_tty_nr(tty_nr: i32) -> Option<Terminal> {
+        let tty_nr = tty_nr as u32;
+        if tty_nr == 0 {
+            return None;
+        }
+
+        Some(Terminal {
+            major: (tty_nr >> 8) & 0xfff,
+            minor: (tty_nr & 0xff) | ((tty_nr >> 12) & 0xfff00),
+        })
+    }
+
+    /// Return the path of the terminal device, resolved through `/proc/tty/drivers`.
+    ///
+    /// Returns `None` if no driver handles the device, or its device node can't be found.
+    pub fn path(&self) -> Result<Option<PathBuf>> {
+        let drivers = read_file(Path::new("/proc/tty/drivers"))?;
+        Ok(self.path_from_drivers(&drivers))
+    }
+
+    fn path_from_drivers(&self, drivers: &str) -> Option<PathBuf> {
+        let driver = drivers.lines()
+            .filter_map(TtyDriver::parse)
+            .find(|d| {
+                d.major == self.major && d.minors.0 <= self.minor && self.minor <= d.minors.1
+            })?;
+
+        if driver.minors.0 == driver.minors.1 {
+            return Some(driver.node);
+        }
+        if driver.kind == "pty:slave" {
+            return Some(driver.node.join(self.minor.to_string()));
+        }
+
+        // Drivers handling a range of minors name their devices inconsistently, e.g. `/dev/tty1`
+        // has minor 1 but `/dev/ttyS0` has minor 64, so check the device number of each candidate
+        let node = driver.node.to_string_lossy();
+        let offset = self.minor - driver.minors.0;
+        let candidates = [format!("{}{}", node, self.minor), format!("{}{}", node, offset)];
+        candidates.iter()
+            .map(PathBuf::from)
+            .find(|path| self.is_device(path))
+    }
+
+    fn is_device(&self, path: &Path) -> bool {
+        use std::os::unix::fs::MetadataExt;
+
+        path.metadata()
+            .map(|metadata| metadata.rdev() == libc::makedev(self.major, self.minor))
+            .unwrap_or(false)
+    }
+}
+
+/// A line of `/proc/tty/drivers`.
+struct TtyDriver {
+    node: PathBuf,
+    major: u32,
+    minors: (u32, u32),
+    kind: String,
+}
+
+impl TtyDriver {
+    fn parse(line: &str) -> Option<TtyDriver> {
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        if fields.len() != 5 {
+            return None;
+        }
+
+        let minors = match fields[3].find('-') {
+            Some(i) => (fields[3][..i].parse().ok()?, fields[3][i + 1..].parse().ok()?),
+            None => {
+                let minor = fields[3].parse().ok()?;
+                (minor, minor)
+            }
+        };
+
+        Some(TtyDriver {
+            node: PathBuf::from(fields[1]),
+            major: fields[2].parse().ok()?,
+            minors,
+            kind: fields[4].to_string(),
+        })
+    }
+}
+
+pub struct Fd {
+    /// Number of fd
+    pub number: i32,
+
+    /// Path to resolved fd
+    pub path: PathBuf,
+
+}
+
+impl Fd {
+    /// Returns the inode number if the fd is a socket.
+    ///
+    /// Sockets are shown as links to `socket:[inode]` in `/proc/[pid]/fd`.
+    pub fn socket_inode(&self) -> Option<u64> {
+        let path = self.path.to_str()?;
+        if path.starts_with("socket:[") && path.ends_with(']') {
+            path[8..path.len() - 1].parse().ok()
+        } else {
+            None
+        }
+    }
+}
+
+
+/// A stable identity for a process, suitable for storing outside of the current program.
+///
+/// PIDs are reused, so a process is identified by its PID and `starttime_ticks`, along with the
+/// boot ID from `/proc/sys/kernel/random/boot_id` as `starttime` repeats across reboots. These
+/// don't depend on the system clock, unlike `Process::create_time`, which moves whenever the clock
+/// is stepped. A key is formatted as `boot_id:pid:starttime_ticks`, and can be parsed back using
+/// `FromStr`.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct ProcessKey {
+    /// Random ID generated by the kernel at boot.
+    pub boot_id: String,
+
+    /// PID of the process.
+    pub pid: PID,
+
+    /// Time the process started after system boot, in clock ticks.
+    pub starttime_ticks: u64,
+}
+
+impl fmt::Display for ProcessKey {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}:{}:{}", self.boot_id, self.pid, self.starttime_ticks)
+    }
+}
+
+impl FromStr for ProcessKey {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let mut parts = s.splitn(3, ':');
+        match (parts.next(),
+               parts.next().map(PID::from_str),
+               parts.next().map(u64::from_str)) {
+            (Some(boot_id), Some(Ok(pid)), Some(Ok(starttime_ticks))) if !boot_id.is_empty() => {
+                Ok(ProcessKey { boot_id: boot_id.to_string(), pid, starttime_ticks })
+            }
+            _ => Err(Error::new(ErrorKind::InvalidInput,
+                                format!("Could not parse process key {:?}", s))),
+        }
+    }
+}
+
+/// Information about a process gathered from `/proc/[pid]/stat`.
+///
+/// More information about specific fields can be found in [proc(5)].
+///
+/// # Field sizes
+///
+/// The manual pages for `proc` define integer sizes using `scanf(3)` format specifiers, which parse
+/// to implementation specific sizes. This is obviously a terrible idea, and so this code makes some
+/// assumptions about the sizes of those specifiers.
+///
+/// These assumptions are backed up by `libc::types::os::arch::posix88::pid_t`, which declares PIDs
+/// to be signed 32 bit integers. `proc(5)` declares that PIDs use the `%d` format specifier.
+///
+/// - `%d` / `%u` - 32 bit signed and unsigned integers
+/// - `%ld` / `%lu` - 64 bit signed and unsigned integers
+///
+/// Rust currently has no support for 128 bit integers ([rfc521]) so `%llu` (used by the `starttime`
+/// and `delayacct_blkio_ticks` fields) is is instead represented by a 64 bit integer, with the hope
+/// that doesn't break.
+///
+/// ### CPU time fields and clock ticks
+///
+/// The CPU time fields are very strange. Inside the Linux kernel they each use the same type
+/// ([array.c:361]) but when printed use different types ([array.c:456]) - the fields `utime`,
+/// `stime` and `gtime` are unsigned integers, whereas `cutime`, `cstime` and `cgtime` are signed
+/// integers.
+///
+/// These values are all returned as a number of clock ticks, which can be divided by
+/// `sysconf(_SC_CLK_TCK)` to get a value in seconds. The `Process` struct does this conversion
+/// automatically, and all CPU time fields use the `f64` type. A corresponding `_ticks` field  (e.g.
+/// `utime_ticks` gives the raw number of ticks as found in `/proc`.
+///
+/// # Unmaintained fields
+///
+/// The `itrealvalue` [20], `nswap` [35] and `cnswap` [36] fields are not maintained, and in some
+/// cases are hardcoded to 0 in the kernel. The `signal` [30], `blocked` [31], `sigignore` [32] and
+/// `sigcatch` [33] fields are included as private fields, but `proc(5)` recommends the use of
+/// `/proc/[pid]/status` instead.
+///
+/// # Examples
+///
+/// ```
+/// psutil::process::Process::new(psutil::getpid()).unwrap();
+/// ```
+///
+/// [array.c:361]: https://github.com/torvalds/linux/blob/master/fs/proc/array.c#L361
+/// [array.c:456]: https://github.com/torvalds/linux/blob/master/fs/proc/array.c#L456
+/// [proc(5)]: http://man7.org/linux/man-pages/man5/proc.5.html
+/// [rfc521]: https://github.com/rust-lang/rfcs/issues/521
+#[derive(Clone,Debug)]
+pub struct Process {
+    /// PID of the process.
+    pub pid: PID,
+
+    /// Real UID of the process.
+    pub uid: UID,
+
+    /// Real GID of the process.
+    pub gid: GID,
+
+    /// Filename of the executable.
+    pub comm: String,
+
+    /// State of the process as an enum.
+    pub state: State,
+
+    /// PID of the parent process.
+    pub ppid: PID,
+
+    /// Process group ID.
+    pub pgrp: i32,
+
+    /// Session ID.
+    pub session: i32,
+
+    /// Controlling terminal of the process, encoding the major and minor device numbers.
+    ///
+    /// Use `Process::tty` to decode it.
+    pub tty_nr: i32,
+
+    /// ID of the foreground group of the controlling terminal.
+    pub tpgid: i32,
+
+    /// Kernel flags for the process.
+    pub flags: u32,
+
+    /// Minor faults.
+    pub minflt: u64,
+
+    /// Minor faults by child processes.
+    pub cminflt: u64,
+
+    /// Major faults.
+    pub majflt: u64,
+
+    /// Major faults by child processes.
+    pub cmajflt: u64,
+
+    /// Time scheduled in user mode (seconds).
+    pub utime: f64,
+
+    /// Time scheduled in user mode (ticks).
+    pub utime_ticks: u64,
+
+    /// Time scheduled in kernel mode (seconds).
+    pub stime: f64,
+
+    /// Time scheduled in kernel mode (ticks).
+    pub stime_ticks: u64,
+
+    /// Time waited-for child processes were scheduled in user mode (seconds).
+    pub cutime: f64,
+
+    /// Time waited-for child processes were scheduled in user mode (ticks).
+    pub cutime_ticks: i64,
+
+    /// Time waited-for child processes were scheduled in kernel mode (seconds).
+    pub cstime: f64,
+
+    /// Time waited-for child processes were scheduled in kernel mode (ticks).
+    pub cstime_ticks: i64,
+
+    /// Priority value (-100..-2 | 0..39).
+    pub priority: i64,
+
+    /// Nice value (-20..19).
+    pub nice: i64,
+
+    /// Number of threads in the process.
+    pub num_threads: i64,
+
+    // Unmaintained field since linux 2.6.17, always 0.
+    // itrealvalue: i64,
+    /// Time the process was started after system boot (seconds).
+    pub starttime: f64,
+
+    /// Time the process was started after system boot (ticks).
+    pub starttime_ticks: u64,
+
+    /// Virtual memory size in bytes.
+    pub vsize: u64,
+
+    /// Resident Set Size (bytes).
+    pub rss: i64,
+
+    /// Current soft limit on process RSS (bytes).
+    pub rsslim: u64,
+
+    // These values are memory addresses
+    startcode: u64,
+    endcode: u64,
+    startstack: u64,
+    kstkesp: u64,
+    kstkeip: u64,
+
+    // Signal bitmaps.
+    // These are obsolete, use `/proc/[pid]/status` instead.
+    signal: u64,
+    blocked: u64,
+    sigignore: u64,
+    sigcatch: u64,
+
+    /// Channel the process is waiting on (address of a system call).
+    pub wchan: u64,
+
+    // Number of pages swapped (not maintained).
+    // pub nswap: u64,
+    //
+    // Number of pages swapped for child processes (not maintained).
+    // pub cnswap: u64,
+    /// Signal sent to parent when process dies.
+    pub exit_signal: i32,
+
+    /// Number of the CPU the process was last executed on.
+    pub processor: i32,
+
+    /// Real-time scheduling priority (0 | 1..99).
+    pub rt_priority: u32,
+
+    /// Scheduling policy.
+    pub policy: u32,
+
+    /// Aggregated block I/O delays (seconds).
+    pub delayacct_blkio: f64,
+
+    /// Aggregated block I/O delays (ticks).
     pub delayacct_blkio_ticks: u64,
 
     /// Guest time of the process (seconds).
@@ -369,21 +1546,26 @@ pub struct Process {
 
     /// The thread's exit status.
     pub exit_code: i32,
+
+    // When this information was read, used to calculate CPU utilisation.
+    sampled_at: Instant,
 }
 
 impl Process {
     /// Attempts to read process information from `/proc/[pid]/stat`.
     ///
-    /// Some additional metadata is read from the permissions on the `/proc/[pid]/`, which defines
-    /// the process UID/GID. The format of `/proc/[pid]/stat` format is defined in proc(5).
+    /// The real UID/GID of the process are read from `/proc/[pid]/status`. The ownership of
+    /// `/proc/[pid]/` can't be used for this, as it reflects the effective IDs and is set to root
+    /// for non-dumpable processes. The format of `/proc/[pid]/stat` format is defined in proc(5).
     pub fn new(pid: PID) -> Result<Process> {
         let path = procfs_path(pid, "stat");
         let stat = try!(read_file(&path));
-        let meta = try!(fs::metadata(procfs_path(pid, "")));
-        Process::new_internal(&stat, meta.uid(), meta.gid(), &path)
+        let status_path = procfs_path(pid, "status");
+        let (uid, gid) = ProcessStatus::parse_real_ids(&read_file(&status_path)?, &status_path)?;
+        Process::new_internal(&stat, uid, gid, &path)
     }
 
-    fn new_internal(stat: &str, file_uid: UID, file_gid: GID, path: &PathBuf) -> Result<Process> {
+    fn new_internal(stat: &str, uid: UID, gid: GID, path: &PathBuf) -> Result<Process> {
         // Read the PID and comm fields separately, as the comm field is delimited by brackets and
         // could contain spaces.
         let (pid_, rest) = match stat.find('(') {
@@ -409,8 +1591,8 @@ impl Process {
         // Read each field into an attribute for a new Process instance
         Ok(Process {
             pid: try_parse!(fields[00]),
-            uid: file_uid,
-            gid: file_gid,
+            uid,
+            gid,
             comm: try_parse!(fields[1]),
             state: try_parse!(fields[2]),
             ppid: try_parse!(fields[3]),
@@ -470,111 +1652,949 @@ impl Process {
             env_start: try_parse!(fields[49]),
             env_end: try_parse!(fields[50]),
             exit_code: try_parse!(fields[51]),
+            sampled_at: Instant::now(),
+        })
+    }
+
+    /// Create a Process by reading its PID from a pidfile.
+    pub fn from_pidfile(path: &Path) -> Result<Process> {
+        Process::new(try!(read_pidfile(&path)))
+    }
+
+    /// Return the time the process was created.
+    ///
+    /// `starttime` is relative to system boot, so it is added to the boot time from `/proc/stat`.
+    /// The boot time is derived from the system clock, so the result changes if the clock is
+    /// stepped; use `key` to identify a process.
+    pub fn create_time(&self) -> Result<SystemTime> {
+        let ticks_per_second = *TICKS_PER_SECOND as u64;
+        let seconds = self.starttime_ticks / ticks_per_second;
+        let nanos = (self.starttime_ticks % ticks_per_second) * 1_000_000_000 / ticks_per_second;
+        Ok(boot_time()? + Duration::from_secs(seconds) + Duration::from_nanos(nanos))
+    }
+
+    /// Return a key identifying the process, which remains valid across reboots.
+    pub fn key(&self) -> Result<ProcessKey> {
+        let boot_id = read_file(Path::new("/proc/sys/kernel/random/boot_id"))?;
+
+        Ok(ProcessKey {
+            boot_id: boot_id.trim().to_string(),
+            pid: self.pid,
+            starttime_ticks: self.starttime_ticks,
         })
     }
 
-    /// Create a Process by reading its PID from a pidfile.
-    pub fn from_pidfile(path: &Path) -> Result<Process> {
-        Process::new(try!(read_pidfile(&path)))
+    /// Return the controlling terminal of the process, if it has one.
+    pub fn tty(&self) -> Option<Terminal> {
+        Terminal::from_tty_nr(self.tty_nr)
+    }
+
+    /// Return the path of the controlling terminal of the process, such as `/dev/pts/0`.
+    ///
+    /// Returns `None` if the process has no controlling terminal or its path can't be resolved.
+    pub fn terminal(&self) -> Result<Option<PathBuf>> {
+        match self.tty() {
+            Some(tty) => tty.path(),
+            None => Ok(None),
+        }
+    }
+
+    /// Return the kernel flags of the process.
+    pub fn flags(&self) -> ProcessFlags {
+        ProcessFlags(self.flags)
+    }
+
+    /// Return the scheduling policy of the process.
+    pub fn policy(&self) -> SchedPolicy {
+        SchedPolicy::from_raw(self.policy)
+    }
+
+    /// Return `true` if the process is a kernel thread.
+    pub fn is_kernel_thread(&self) -> bool {
+        self.flags().contains(ProcessFlags::KTHREAD)
+    }
+
+    /// Return `true` if the process was alive at the time it was read.
+    pub fn is_alive(&self) -> bool {
+        match self.state {
+            State::Zombie => false,
+            _ => true,
+        }
+    }
+
+    /// Return the CPU utilisation of the process since it was last read, as a percentage.
+    ///
+    /// The process is read again from `/proc/[pid]/stat`, and the user and system time used since
+    /// the previous read is divided by the elapsed wall-clock time and the number of CPUs, giving a
+    /// value between 0 and 100. `self` is then updated with the new information, so calling this
+    /// repeatedly gives the utilisation over each interval.
+    ///
+    /// Returns a `NotFound` error if the process has exited or the PID has been reused by another
+    /// process.
+    pub fn cpu_percent(&mut self) -> Result<f32> {
+        let current = Process::new(self.pid)?;
+        if current != *self {
+            return Err(Error::new(ErrorKind::NotFound,
+                                  format!("Process {} has been replaced", self.pid)));
+        }
+
+        let elapsed = current.sampled_at.duration_since(self.sampled_at);
+        let elapsed = elapsed.as_secs() as f64 + elapsed.subsec_nanos() as f64 / 1e9;
+        let ticks = (current.utime_ticks + current.stime_ticks)
+            .saturating_sub(self.utime_ticks + self.stime_ticks);
+        *self = current;
+
+        if elapsed <= 0.0 {
+            return Ok(0.0);
+        }
+        let busy = ticks as f64 / *TICKS_PER_SECOND;
+        Ok((busy / elapsed / cpu_count()? as f64 * 100.0).min(100.0) as f32)
+    }
+
+    /// Split the contents of `/proc/[pid]/cmdline` into arguments.
+    ///
+    /// Arguments are separated by NUL bytes, but processes that rewrite their argv (e.g. to set
+    /// a title) often separate them with spaces instead. Like psutil, fall back to splitting on
+    /// spaces if the contents don't end with a NUL, or if they contain only one argument.
+    fn cmdline_internal(cmdline: &[u8]) -> Vec<OsString> {
+        let sep = if cmdline.ends_with(b"\0") { b'\0' } else { b' ' };
+        let cmdline = if cmdline.ends_with(&[sep]) {
+            &cmdline[..cmdline.len() - 1]
+        } else {
+            cmdline
+        };
+
+        let mut args: Vec<&[u8]> = cmdline.split(|&b| b == sep).collect();
+        if sep == b'\0' && args.len() == 1 && cmdline.contains(&b' ') {
+            args = cmdline.split(|&b| b == b' ').collect();
+        }
+
+        args.into_iter().map(|arg| OsStr::from_bytes(arg).to_os_string()).collect()
+    }
+
+    /// Read `/proc/[pid]/cmdline` as a vector of arguments, without any conversion to UTF-8.
+    ///
+    /// Returns `None` if `/proc/[pid]/cmdline` is empty, as it is for kernel threads and zombies.
+    pub fn cmdline_os(&self) -> Result<Option<Vec<OsString>>> {
+        let cmdline = fs::read(procfs_path(self.pid, "cmdline"))?;
+
+        if cmdline.is_empty() {
+            return Ok(None);
+        }
+        Ok(Some(Process::cmdline_internal(&cmdline)))
+    }
+
+    /// Read `/proc/[pid]/cmdline` as a vector of arguments.
+    ///
+    /// Arguments that aren't valid UTF-8 are converted lossily; use `cmdline_os` to read them
+    /// exactly. Returns `None` if `/proc/[pid]/cmdline` is empty.
+    pub fn cmdline_vec(&self) -> Result<Option<Vec<String>>> {
+        Ok(self.cmdline_os()?.map(|args| {
+            args.iter().map(|arg| arg.to_string_lossy().into_owned()).collect()
+        }))
+    }
+
+    /// Return the result of `cmdline_vec` as a String, quoting arguments as a shell would need.
+    pub fn cmdline(&self) -> Result<Option<String>> {
+        Ok(self.cmdline_vec()?.map(|args| {
+            args.iter().map(|arg| shell_quote(arg)).collect::<Vec<String>>().join(" ")
+        }))
+    }
+
+    /// Read the path of the process' current working directory.
+    pub fn cwd(&self) -> Result<PathBuf> {
+        read_link(procfs_path(self.pid, "cwd"))
+    }
+
+    /// Read the absolute path of the process
+    pub fn exe(&self) -> Result<PathBuf> {
+        read_link(procfs_path(self.pid, "exe"))
+    }
+
+    fn environ_internal(file_contents: &str) -> Result<HashMap<String, String>> {
+        let mut ret = HashMap::new();
+        for s in file_contents.split_terminator('\0') {
+            let vv: Vec<&str> = s.splitn(2, "=").collect();
+            if vv.len() != 2 {
+                return Err(Error::new(ErrorKind::InvalidInput,
+                                  format!("Item {} has too few fields", s)));
+            }
+            ret.insert(vv[0].to_owned(), vv[1].to_owned());
+        }
+
+        Ok(ret)
+    }
+    
+    pub fn environ(&self) -> Result<HashMap<String, String>> {
+        let path = procfs_path(self.pid, "environ");
+        let env = try!(read_file(&path));
+        Process::environ_internal(&env)
+    }
+
+    /// Read `/proc/[pid]/environ`, keeping the order of variables and any that aren't UTF-8.
+    ///
+    /// Unlike `environ`, entries without an `=` are returned in `Environ::malformed` rather than
+    /// causing an error.
+    pub fn environ_os(&self) -> Result<Environ> {
+        Ok(Environ::parse(&fs::read(procfs_path(self.pid, "environ"))?))
+    }
+
+    /// Reads `/proc/[pid]/status` into a struct.
+    pub fn status(&self) -> Result<ProcessStatus> {
+        ProcessStatus::new(self.pid)
+    }
+
+    /// Read the real, effective, saved set and filesystem UIDs of the process.
+    pub fn uids(&self) -> Result<Uids> {
+        Ok(self.status()?.uids)
+    }
+
+    /// Read the real, effective, saved set and filesystem GIDs of the process.
+    pub fn gids(&self) -> Result<Gids> {
+        Ok(self.status()?.gids)
+    }
+
+    /// Return the name of the user that owns the process (the real UID).
+    ///
+    /// The name is looked up using `getpwuid_r(3)`, and a `NotFound` error is returned if the UID
+    /// has no entry in the user database.
+    pub fn username(&self) -> Result<String> {
+        let mut buffer: Vec<libc::c_char> = vec![0; 1024];
+
+        loop {
+            let mut passwd: libc::passwd = unsafe { mem::zeroed() };
+            let mut result = ptr::null_mut();
+            let errno = unsafe {
+                libc::getpwuid_r(self.uid, &mut passwd, buffer.as_mut_ptr(), buffer.len(),
+                                 &mut result)
+            };
+
+            if errno == libc::ERANGE {
+                let len = buffer.len() * 2;
+                buffer.resize(len, 0);
+            } else if errno != 0 {
+                return Err(Error::from_raw_os_error(errno));
+            } else if result.is_null() {
+                return Err(Error::new(ErrorKind::NotFound,
+                                      format!("No user with UID {}", self.uid)));
+            } else {
+                let name = unsafe { CStr::from_ptr(passwd.pw_name) };
+                return Ok(name.to_string_lossy().into_owned());
+            }
+        }
+    }
+
+    /// Reads `/proc/[pid]/statm` into a struct.
+    pub fn memory(&self) -> Result<Memory> {
+        Memory::new(self.pid)
+    }
+
+    /// Reads `/proc/[pid]/smaps_rollup` into a struct.
+    ///
+    /// This is much slower than `memory()`, and requires the same permissions as tracing the
+    /// process.
+    pub fn memory_full_info(&self) -> Result<MemoryFullInfo> {
+        MemoryFullInfo::new(self.pid)
+    }
+
+    /// Reads the memory mappings of the process from `/proc/[pid]/smaps`.
+    ///
+    /// If `grouped` is `true`, mappings of the same file (or pseudo-path) are added together, and
+    /// the `region` field of each entry is `None`.
+    pub fn memory_maps(&self, grouped: bool) -> Result<Vec<MemoryMap>> {
+        let path = procfs_path(self.pid, "smaps");
+        let maps = MemoryMap::parse(&fs::read(&path)?, &path)?;
+        Ok(if grouped { MemoryMap::group(maps) } else { maps })
+    }
+
+    /// Reads `/proc/[pid]/io` into a struct.
+    ///
+    /// Returns a `PermissionDenied` error for processes owned by other users.
+    pub fn io_counters(&self) -> Result<IoCounters> {
+        IoCounters::new(self.pid)
+    }
+
+    /// Return the parent process.
+    ///
+    /// Returns `None` if the process has no parent, or if the parent has exited (in which case
+    /// its PID may have been reused by a process that started later).
+    pub fn parent(&self) -> Result<Option<Process>> {
+        if self.ppid == 0 {
+            return Ok(None);
+        }
+
+        match Process::new(self.ppid) {
+            Ok(parent) => {
+                if parent.starttime_ticks <= self.starttime_ticks {
+                    Ok(Some(parent))
+                } else {
+                    Ok(None)
+                }
+            }
+            Err(ref e) if e.kind() == ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Return the children of the process, or all of its descendants if `recursive` is `true`.
+    ///
+    /// Children are read from `/proc/[pid]/task/[tid]/children` when the kernel supports it
+    /// (`CONFIG_PROC_CHILDREN`), and otherwise by scanning every process in `/proc`.
+    pub fn children(&self, recursive: bool) -> Result<Vec<Process>> {
+        let children = match self.children_from_tasks(recursive)? {
+            Some(children) => children,
+            None => {
+                let tree = ProcessTree::new()?;
+                let children = if recursive {
+                    tree.descendants(self.pid)
+                } else {
+                    tree.children(self.pid)
+                };
+                children.into_iter().cloned().collect()
+            }
+        };
+
+        // Processes started before this one can't be its children, so their parent has exited
+        // and the PID has been reused.
+        Ok(children
+            .into_iter()
+            .filter(|child| child.starttime_ticks >= self.starttime_ticks)
+            .collect())
+    }
+
+    /// Read children from `/proc/[pid]/task/[tid]/children`, returning `None` if it doesn't exist.
+    fn children_from_tasks(&self, recursive: bool) -> Result<Option<Vec<Process>>> {
+        let mut children = Vec::new();
+        let mut pending = vec![self.pid];
+        let mut visited = HashSet::new();
+        visited.insert(self.pid);
+
+        while let Some(pid) = pending.pop() {
+            let pids = match child_pids(pid) {
+                Ok(pids) => pids,
+                Err(ref e) if e.kind() == ErrorKind::NotFound && pid == self.pid => return Ok(None),
+                // The process exited after being listed as a child
+                Err(ref e) if e.kind() == ErrorKind::NotFound => continue,
+                Err(e) => return Err(e),
+            };
+
+            // PIDs can be reused while the tree is read, which could otherwise create a cycle
+            for pid in pids.into_iter().filter(|&pid| visited.insert(pid)) {
+                match Process::new(pid) {
+                    Ok(child) => children.push(child),
+                    Err(ref e) if e.kind() == ErrorKind::NotFound => continue,
+                    Err(e) => return Err(e),
+                }
+                if recursive {
+                    pending.push(pid);
+                }
+            }
+        }
+
+        Ok(Some(children))
+    }
+
+    /// Reads each thread of the process from `/proc/[pid]/task`.
+    ///
+    /// Threads that exit while the directory is being read are skipped.
+    pub fn threads(&self) -> Result<Vec<Thread>> {
+        let mut threads = Vec::new();
+
+        for entry in read_dir(procfs_path(self.pid, "task"))? {
+            let path = entry?.path();
+            let tid = match path.file_name().and_then(|name| name.to_str()) {
+                Some(name) => try_parse!(name, PID::from_str),
+                None => continue,
+            };
+
+            let stat_path = path.join("stat");
+            let (stat, comm) = match (read_file(&stat_path), read_file(&path.join("comm"))) {
+                (Ok(stat), Ok(comm)) => (stat, comm),
+                (Err(ref e), _) | (_, Err(ref e)) if e.kind() == ErrorKind::NotFound => continue,
+                (Err(e), _) | (_, Err(e)) => return Err(e),
+            };
+            let stat = Process::new_internal(&stat, self.uid, self.gid, &stat_path)?;
+
+            threads.push(Thread {
+                tid,
+                name: comm.trim_end_matches('\n').to_string(),
+                stat,
+            });
+        }
+
+        Ok(threads)
+    }
+
+    /// Reads `/proc/[pid]/fd` directory
+    pub fn open_fds(&self) -> Result<Vec<Fd>> {
+        let mut fds = Vec::new();
+        let entry_set = try!(read_dir(procfs_path(self.pid, "fd")));
+        for entry in entry_set {
+            if let Ok(entry) = entry {
+                let path = entry.path();
+                let fd_number =  try!(path.file_name().ok_or(parse_error("Could not read /proc entry", &path)));
+                if let Ok(fd_path) = read_link(&path) {
+                    fds.push(Fd{number: fd_number.to_string_lossy().parse::<i32>().unwrap(), path: fd_path})
+                }
+            }
+        }
+
+        Ok(fds)
+    }
+
+    /// Returns the sockets opened by the process.
+    ///
+    /// The socket inodes in `/proc/[pid]/fd` are matched against the connection tables in
+    /// `/proc/[pid]/net`, and the `fd` field of each connection is set. A socket shared by
+    /// several file descriptors is returned once for each of them.
+    pub fn connections(&self, kind: ConnectionKind) -> Result<Vec<Connection>> {
+        let mut fds: HashMap<u64, Vec<i32>> = HashMap::new();
+        for fd in self.open_fds()? {
+            if let Some(inode) = fd.socket_inode() {
+                fds.entry(inode).or_default().push(fd.number);
+            }
+        }
+        if fds.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let mut connections = Vec::new();
+        for connection in read_connections(&procfs_path(self.pid, "net"), kind)? {
+            if let Some(numbers) = fds.get(&connection.inode) {
+                for number in numbers {
+                    connections.push(Connection { fd: Some(*number), ..connection.clone() });
+                }
+            }
+        }
+
+        Ok(connections)
+    }
+
+    /// Return a `NotFound` error if the process has exited or its PID has been reused.
+    fn check_identity(&self) -> Result<()> {
+        if Process::new(self.pid)? != *self {
+            return Err(Error::new(ErrorKind::NotFound,
+                                  format!("Process {} has been replaced", self.pid)));
+        }
+        Ok(())
+    }
+
+    /// Send a signal to the process.
+    ///
+    /// The process is read again first, and a `NotFound` error is returned if it has exited or
+    /// its PID has been reused by another process, so that the signal is never sent to the wrong
+    /// process.
+    pub fn send_signal(&self, signal: Signal) -> Result<()> {
+        self.check_identity()?;
+        match unsafe { kill(self.pid, signal as i32) } {
+            0 => Ok(()),
+            -1 => Err(last_os_error(self.pid)),
+            _ => unreachable!(),
+        }
+    }
+
+    /// Wait for the process to exit, returning its exit code if it can be determined.
+    ///
+    /// Children of the calling process are reaped using `waitpid(2)`. Other processes are polled
+    /// until they exit; their exit code is only known if they are seen as a zombie before their
+    /// parent reaps them, and the caller is allowed to trace them. The exit code of a process
+    /// killed by a signal is the negated signal number.
+    ///
+    /// Returns a `TimedOut` error if the process is still running after `timeout`.
+    pub fn wait(&self, timeout: Option<Duration>) -> Result<Option<i32>> {
+        let deadline = timeout.map(|timeout| Instant::now() + timeout);
+        let mut delay = Duration::from_millis(1);
+
+        loop {
+            if let Some(exit_code) = self.try_wait()? {
+                return Ok(exit_code);
+            }
+
+            if let Some(deadline) = deadline {
+                let now = Instant::now();
+                if now >= deadline {
+                    return Err(Error::new(ErrorKind::TimedOut,
+                                          format!("Process {} is still running", self.pid)));
+                }
+                delay = delay.min(deadline - now);
+            }
+            thread::sleep(delay);
+            delay = (delay * 2).min(Duration::from_millis(40));
+        }
+    }
+
+    /// Check if the process has exited without blocking.
+    ///
+    /// Returns `None` if the process is still running, or `Some` with the exit code if known.
+    fn try_wait(&self) -> Result<Option<Option<i32>>> {
+        let current = match Process::new(self.pid) {
+            Ok(current) => current,
+            Err(ref e) if e.kind() == ErrorKind::NotFound => return Ok(Some(None)),
+            Err(e) => return Err(e),
+        };
+
+        // If the PID has been reused the process has exited, and the PID may now belong to
+        // another child that must not be reaped
+        if current != *self {
+            return Ok(Some(None));
+        }
+
+        if self.ppid == ::getpid() {
+            let mut status = 0;
+            match unsafe { libc::waitpid(self.pid, &mut status, libc::WNOHANG) } {
+                0 => return Ok(None),
+                -1 => {
+                    let error = Error::last_os_error();
+                    // ECHILD means the child has already been reaped elsewhere
+                    if error.raw_os_error() != Some(libc::ECHILD) {
+                        return Err(error);
+                    }
+                }
+                _ => return Ok(Some(decode_wait_status(status))),
+            }
+        }
+
+        if current.is_alive() {
+            return Ok(None);
+        }
+        if current.exit_code != 0 {
+            return Ok(Some(decode_wait_status(current.exit_code)));
+        }
+
+        // The kernel reports an exit code of 0 unless the caller has ptrace read access to the
+        // process. Permission to signal it needs the same user (or `CAP_KILL`), so use that to
+        // tell whether a 0 is real.
+        match unsafe { kill(self.pid, 0) } {
+            0 => Ok(Some(Some(0))),
+            _ => {
+                let error = last_os_error(self.pid);
+                match error.kind() {
+                    ErrorKind::PermissionDenied | ErrorKind::NotFound => Ok(Some(None)),
+                    _ => Err(error),
+                }
+            }
+        }
+    }
+
+    /// Send SIGKILL to the process.
+    pub fn kill(&self) -> Result<()> {
+        self.send_signal(Signal::Kill)
+    }
+
+    /// Send SIGTERM to the process.
+    pub fn terminate(&self) -> Result<()> {
+        self.send_signal(Signal::Term)
+    }
+
+    /// Send SIGSTOP to the process.
+    pub fn suspend(&self) -> Result<()> {
+        self.send_signal(Signal::Stop)
+    }
+
+    /// Send SIGCONT to the process.
+    pub fn resume(&self) -> Result<()> {
+        self.send_signal(Signal::Cont)
     }
 
-    /// Return `true` if the process was alive at the time it was read.
-    pub fn is_alive(&self) -> bool {
-        match self.state {
-            State::Zombie => false,
-            _ => true,
+    /// Set the nice value of the process.
+    ///
+    /// Lowering the nice value requires `CAP_SYS_NICE`, otherwise a `PermissionDenied` error is
+    /// returned. The `nice` field is not updated; use `Process::new` to read the new value.
+    pub fn set_nice(&self, nice: i32) -> Result<()> {
+        self.check_identity()?;
+        match unsafe { libc::setpriority(libc::PRIO_PROCESS, self.pid as libc::id_t, nice) } {
+            0 => Ok(()),
+            _ => Err(last_os_error(self.pid)),
+        }
+    }
+
+    /// Return the I/O scheduling class and priority of the process.
+    pub fn ionice(&self) -> Result<IoNice> {
+        self.check_identity()?;
+        let ioprio = unsafe {
+            libc::syscall(libc::SYS_ioprio_get, IOPRIO_WHO_PROCESS, libc::c_long::from(self.pid))
+        };
+        if ioprio == -1 {
+            return Err(last_os_error(self.pid));
         }
+        IoNice::from_raw(ioprio as i32)
     }
 
-    /// Read `/proc/[pid]/cmdline` as a vector.
+    /// Set the I/O scheduling class and priority of the process.
     ///
-    /// Returns `Err` if `/proc/[pid]/cmdline` is empty.
-    pub fn cmdline_vec(&self) -> Result<Option<Vec<String>>> {
-        let cmdline = try!(read_file(&procfs_path(self.pid, "cmdline")));
+    /// Returns an `InvalidInput` error if the value is out of range for the class.
+    pub fn set_ionice(&self, ionice: IoNice) -> Result<()> {
+        let ioprio = ionice.to_raw()?;
+        self.check_identity()?;
+        match unsafe {
+            libc::syscall(libc::SYS_ioprio_set,
+                          IOPRIO_WHO_PROCESS,
+                          libc::c_long::from(self.pid),
+                          libc::c_long::from(ioprio))
+        } {
+            0 => Ok(()),
+            _ => Err(last_os_error(self.pid)),
+        }
+    }
 
-        if cmdline.is_empty() {
-            return Ok(None);
+    /// Return the CPUs the process is allowed to run on.
+    pub fn cpu_affinity(&self) -> Result<Vec<usize>> {
+        self.check_identity()?;
+        let mut set: libc::cpu_set_t = unsafe { mem::zeroed() };
+        let size = mem::size_of::<libc::cpu_set_t>();
+        if unsafe { libc::sched_getaffinity(self.pid, size, &mut set) } == -1 {
+            return Err(last_os_error(self.pid));
         }
-        // Split terminator skips empty trailing substrings.
-        let split = cmdline.split_terminator(|c: char| c == '\0' || c == ' ');
 
-        // `split` returns a vector of slices viewing `cmdline`, so they
-        // get mapped to actual strings before being returned as a vector.
-        Ok(Some(split.map(|x| x.to_string()).collect()))
+        Ok((0..libc::CPU_SETSIZE as usize)
+            .filter(|&cpu| unsafe { libc::CPU_ISSET(cpu, &set) })
+            .collect())
     }
 
-    /// Return the result of `cmdline_vec` as a String.
-    pub fn cmdline(&self) -> Result<Option<String>> {
-        Ok(try!(self.cmdline_vec()).and_then(|c| Some(c.join(" "))))
+    /// Restrict the process to run on the given CPUs.
+    ///
+    /// Returns an `InvalidInput` error if `cpus` contains a CPU number too large for a `cpu_set_t`,
+    /// or if none of the CPUs can be used. The kernel ignores CPUs that are offline or don't exist
+    /// as long as at least one usable CPU is given.
+    pub fn set_cpu_affinity(&self, cpus: &[usize]) -> Result<()> {
+        let mut set: libc::cpu_set_t = unsafe { mem::zeroed() };
+        for &cpu in cpus {
+            if cpu >= libc::CPU_SETSIZE as usize {
+                return Err(Error::new(ErrorKind::InvalidInput, format!("Invalid CPU {}", cpu)));
+            }
+            unsafe { libc::CPU_SET(cpu, &mut set) };
+        }
+
+        self.check_identity()?;
+        let size = mem::size_of::<libc::cpu_set_t>();
+        if unsafe { libc::sched_setaffinity(self.pid, size, &set) } == -1 {
+            return Err(last_os_error(self.pid));
+        }
+        Ok(())
     }
 
-    /// Read the path of the process' current working directory.
-    pub fn cwd(&self) -> Result<PathBuf> {
-        read_link(procfs_path(self.pid, "cwd"))
+    /// Return the soft and hard limits of the process for a resource.
+    pub fn rlimit(&self, resource: Resource) -> Result<RLimit> {
+        self.check_identity()?;
+        let mut rlimit: libc::rlimit = unsafe { mem::zeroed() };
+        let resource = resource.to_raw() as _;
+        if unsafe { libc::prlimit(self.pid, resource, ptr::null(), &mut rlimit) } == -1 {
+            return Err(last_os_error(self.pid));
+        }
+
+        Ok(RLimit {
+            soft: rlim_from_raw(rlimit.rlim_cur),
+            hard: rlim_from_raw(rlimit.rlim_max),
+        })
     }
 
-    /// Read the absolute path of the process
-    pub fn exe(&self) -> Result<PathBuf> {
-        read_link(procfs_path(self.pid, "exe"))
+    /// Set the soft and hard limits of the process for a resource.
+    ///
+    /// Raising the hard limit requires `CAP_SYS_RESOURCE`, otherwise a `PermissionDenied` error is
+    /// returned.
+    pub fn set_rlimit(&self, resource: Resource, limit: RLimit) -> Result<()> {
+        let rlimit = libc::rlimit {
+            rlim_cur: rlim_to_raw(limit.soft),
+            rlim_max: rlim_to_raw(limit.hard),
+        };
+
+        self.check_identity()?;
+        let resource = resource.to_raw() as _;
+        if unsafe { libc::prlimit(self.pid, resource, &rlimit, ptr::null_mut()) } == -1 {
+            return Err(last_os_error(self.pid));
+        }
+        Ok(())
     }
+}
 
-    fn environ_internal(file_contents: &str) -> Result<HashMap<String, String>> {
-        let mut ret = HashMap::new();
-        for s in file_contents.split_terminator('\0') {
-            let vv: Vec<&str> = s.splitn(2, "=").collect();
-            if vv.len() != 2 {
-                return Err(Error::new(ErrorKind::InvalidInput,
-                                  format!("Item {} has too few fields", s)));
+impl PartialEq for Process {
+    // Compares processes using their PID and `starttime` as an identity.
+    fn eq(&self, other: &Process) -> bool {
+        (self.pid == other.pid) && (self.starttime == other.starttime)
+    }
+}
+
+/// A thread of a process, read from `/proc/[pid]/task/[tid]/`.
+#[derive(Clone,Debug)]
+pub struct Thread {
+    /// Thread ID.
+    pub tid: PID,
+
+    /// Name of the thread, from `/proc/[pid]/task/[tid]/comm`.
+    pub name: String,
+
+    /// All information from `/proc/[pid]/task/[tid]/stat`, which has the same format as
+    /// `/proc/[pid]/stat`, including the thread's state and CPU times.
+    pub stat: Process,
+}
+
+/// Read the PIDs of the children of each thread of a process.
+fn child_pids(pid: PID) -> Result<Vec<PID>> {
+    let mut pids = Vec::new();
+
+    for entry in read_dir(procfs_path(pid, "task"))? {
+        let path = entry?.path().join("children");
+        let children = match read_file(&path) {
+            Ok(children) => children,
+            // The thread exited after the directory was listed
+            Err(ref e) if e.kind() == ErrorKind::NotFound => continue,
+            Err(e) => return Err(e),
+        };
+        for child in children.split_whitespace() {
+            pids.push(try_parse!(child, PID::from_str));
+        }
+    }
+
+    Ok(pids)
+}
+
+/// A snapshot of all processes, which can be navigated by parent and children.
+///
+/// # Examples
+///
+/// ```
+/// let tree = psutil::process::ProcessTree::new().unwrap();
+/// for child in tree.children(1) {
+///     println!("{} {}", child.pid, child.comm);
+/// }
+/// ```
+#[derive(Clone,Debug)]
+pub struct ProcessTree {
+    processes: HashMap<PID, Process>,
+    children: HashMap<PID, Vec<PID>>,
+}
+
+impl ProcessTree {
+    /// Build a tree from the processes in `/proc`.
+    ///
+    /// Unlike `all()`, processes that exit while `/proc` is being read are left out of the tree.
+    pub fn new() -> Result<ProcessTree> {
+        let mut processes = Vec::new();
+
+        for entry in read_dir("/proc")? {
+            let path = entry?.path();
+            let pid = match path.file_name().and_then(|name| name.to_str()) {
+                Some(name) => match PID::from_str(name) {
+                    Ok(pid) => pid,
+                    Err(_) => continue,
+                },
+                None => continue,
+            };
+
+            match Process::new(pid) {
+                Ok(process) => processes.push(process),
+                Err(ref e) if e.kind() == ErrorKind::NotFound => continue,
+                Err(e) => return Err(e),
             }
-            ret.insert(vv[0].to_owned(), vv[1].to_owned());
         }
 
-        Ok(ret)
+        Ok(ProcessTree::from_processes(processes))
     }
-    
-    pub fn environ(&self) -> Result<HashMap<String, String>> {
-        let path = procfs_path(self.pid, "environ");
-        let env = try!(read_file(&path));
-        Process::environ_internal(&env)
+
+    /// Build a tree from a list of processes.
+    pub fn from_processes(processes: Vec<Process>) -> ProcessTree {
+        let mut children: HashMap<PID, Vec<PID>> = HashMap::new();
+        for process in &processes {
+            children.entry(process.ppid).or_default().push(process.pid);
+        }
+        for pids in children.values_mut() {
+            pids.sort();
+        }
+
+        ProcessTree {
+            processes: processes.into_iter().map(|p| (p.pid, p)).collect(),
+            children,
+        }
     }
 
-    /// Reads `/proc/[pid]/statm` into a struct.
-    pub fn memory(&self) -> Result<Memory> {
-        Memory::new(self.pid)
+    /// Return the process with the given PID.
+    pub fn get(&self, pid: PID) -> Option<&Process> {
+        self.processes.get(&pid)
     }
 
-    /// Reads `/proc/[pid]/fd` directory
-    pub fn open_fds(&self) -> Result<Vec<Fd>> {
-        let mut fds = Vec::new();
-        let entry_set = try!(read_dir(procfs_path(self.pid, "fd")));
-        for entry in entry_set {
-            if let Ok(entry) = entry {
-                let path = entry.path();
-                let fd_number =  try!(path.file_name().ok_or(parse_error("Could not read /proc entry", &path)));
-                if let Ok(fd_path) = read_link(&path) {
-                    fds.push(Fd{number: fd_number.to_string_lossy().parse::<i32>().unwrap(), path: fd_path})
-                }
+    /// Return the parent of the process with the given PID.
+    pub fn parent(&self, pid: PID) -> Option<&Process> {
+        self.get(pid).and_then(|process| self.get(process.ppid))
+    }
+
+    /// Return the direct children of the process with the given PID, ordered by PID.
+    pub fn children(&self, pid: PID) -> Vec<&Process> {
+        self.children
+            .get(&pid)
+            .map(|pids| pids.iter().filter_map(|pid| self.get(*pid)).collect())
+            .unwrap_or_default()
+    }
+
+    /// Return all descendants of the process with the given PID, depth first.
+    pub fn descendants(&self, pid: PID) -> Vec<&Process> {
+        let mut descendants = Vec::new();
+        let mut visited = HashSet::new();
+        visited.insert(pid);
+        self.walk_from(pid, 0, &mut visited, &mut |process, _| descendants.push(process));
+        descendants
+    }
+
+    /// Return the processes whose parent is not in the tree, ordered by PID.
+    ///
+    /// These are usually `init` (PID 1) and `kthreadd` (PID 2).
+    pub fn roots(&self) -> Vec<&Process> {
+        let mut roots: Vec<&Process> = self.processes
+            .values()
+            .filter(|process| !self.processes.contains_key(&process.ppid))
+            .collect();
+        roots.sort_by_key(|process| process.pid);
+        roots
+    }
+
+    /// Call `f` for each process in the tree, depth first, along with its depth below the root.
+    ///
+    /// PID reuse while `/proc` is read can make `ppid` links form a cycle that can't be reached
+    /// from a root, so the lowest PID in each cycle is walked as an extra root after the others.
+    pub fn walk<F: FnMut(&Process, usize)>(&self, mut f: F) {
+        let mut visited: HashSet<PID> = self.roots().iter().map(|root| root.pid).collect();
+        for root in self.roots() {
+            f(root, 0);
+            self.walk_from(root.pid, 1, &mut visited, &mut f);
+        }
+        for root in self.sorted() {
+            if visited.insert(root.pid) {
+                f(root, 0);
+                self.walk_from(root.pid, 1, &mut visited, &mut f);
             }
         }
+    }
 
-        Ok(fds)
+    /// Return every process in the tree, ordered by PID.
+    fn sorted(&self) -> Vec<&Process> {
+        let mut processes: Vec<&Process> = self.processes.values().collect();
+        processes.sort_by_key(|process| process.pid);
+        processes
     }
 
-    /// Send SIGKILL to the process.
-    pub fn kill(&self) -> Result<()> {
-        match unsafe { kill(self.pid, SIGKILL) } {
-            0 => Ok(()),
-            -1 => Err(Error::last_os_error()),
-            _ => unreachable!(),
+    /// Return the children of `pid` that haven't been visited yet, marking them as visited.
+    ///
+    /// `all()` doesn't read `/proc` atomically, so a PID reused while it runs can make the
+    /// `ppid` links form a cycle.
+    fn unvisited_children(&self, pid: PID, visited: &mut HashSet<PID>) -> Vec<&Process> {
+        self.children(pid).into_iter().filter(|child| visited.insert(child.pid)).collect()
+    }
+
+    fn walk_from<'a, F>(&'a self, pid: PID, depth: usize, visited: &mut HashSet<PID>, f: &mut F)
+        where F: FnMut(&'a Process, usize)
+    {
+        for child in self.unvisited_children(pid, visited) {
+            f(child, depth);
+            self.walk_from(child.pid, depth + 1, visited, f);
+        }
+    }
+
+    fn fmt_children(&self,
+                    f: &mut fmt::Formatter,
+                    pid: PID,
+                    prefix: &str,
+                    visited: &mut HashSet<PID>)
+                    -> fmt::Result {
+        let children = self.unvisited_children(pid, visited);
+        for (i, child) in children.iter().enumerate() {
+            let last = i == children.len() - 1;
+            let (branch, indent) = if last { ("└─ ", "   ") } else { ("├─ ", "│  ") };
+            writeln!(f, "{}{}{} {}", prefix, branch, child.pid, child.comm)?;
+            let prefix = format!("{}{}", prefix, indent);
+            self.fmt_children(f, child.pid, &prefix, visited)?;
         }
+        Ok(())
     }
 }
 
-impl PartialEq for Process {
-    // Compares processes using their PID and `starttime` as an identity.
-    fn eq(&self, other: &Process) -> bool {
-        (self.pid == other.pid) && (self.starttime == other.starttime)
+/// Formats the tree in the style of `pstree`.
+///
+/// ```text
+/// 1 systemd
+/// ├─ 512 sshd
+/// │  └─ 1024 bash
+/// └─ 600 cron
+/// ```
+impl fmt::Display for ProcessTree {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut visited: HashSet<PID> = self.roots().iter().map(|root| root.pid).collect();
+        for root in self.roots() {
+            writeln!(f, "{} {}", root.pid, root.comm)?;
+            self.fmt_children(f, root.pid, "", &mut visited)?;
+        }
+        // Processes in a `ppid` cycle, as in `walk`
+        for root in self.sorted() {
+            if visited.insert(root.pid) {
+                writeln!(f, "{} {}", root.pid, root.comm)?;
+                self.fmt_children(f, root.pid, "", &mut visited)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The processes that exited with their exit codes, and the processes still running.
+pub type WaitProcs = (Vec<(Process, Option<i32>)>, Vec<Process>);
+
+/// Wait for several processes to exit, calling `callback` for each one as it does.
+///
+/// Returns the processes that exited along with their exit codes (see `Process::wait`), and the
+/// processes that were still running after `timeout`. This is useful for stopping a group of
+/// processes gracefully, by sending each SIGTERM, waiting, and then killing any that remain.
+///
+/// ```no_run
+/// use std::time::Duration;
+/// use psutil::process::{all, wait_procs};
+///
+/// let workers: Vec<_> = all().unwrap().into_iter().filter(|p| p.comm == "worker").collect();
+/// for worker in &workers {
+///     worker.terminate().unwrap();
+/// }
+/// let (_, alive) = wait_procs(&workers, Some(Duration::from_secs(3)), |p, code| {
+///     println!("{} exited with {:?}", p.pid, code);
+/// }).unwrap();
+/// for worker in &alive {
+///     worker.kill().unwrap();
+/// }
+/// ```
+pub fn wait_procs<F>(processes: &[Process],
+                     timeout: Option<Duration>,
+                     mut callback: F)
+                     -> Result<WaitProcs>
+    where F: FnMut(&Process, Option<i32>)
+{
+    let deadline = timeout.map(|timeout| Instant::now() + timeout);
+    let mut delay = Duration::from_millis(1);
+    let mut gone = Vec::new();
+    let mut alive = processes.to_vec();
+
+    loop {
+        let mut still_alive = Vec::new();
+        for process in alive {
+            match process.try_wait()? {
+                Some(exit_code) => {
+                    callback(&process, exit_code);
+                    gone.push((process, exit_code));
+                }
+                None => still_alive.push(process),
+            }
+        }
+        alive = still_alive;
+
+        if alive.is_empty() {
+            break;
+        }
+        if let Some(deadline) = deadline {
+            let now = Instant::now();
+            if now >= deadline {
+                break;
+            }
+            delay = delay.min(deadline - now);
+        }
+        thread::sleep(delay);
+        delay = (delay * 2).min(Duration::from_millis(40));
     }
+
+    Ok((gone, alive))
 }
 
 /// Return a vector of all processes in `/proc`.
@@ -641,6 +2661,436 @@ mod unit_tests {
         assert_eq!(p.starttime_ticks, 9);
     }
 
+    #[test]
+    fn fd_socket_inode() {
+        let socket = Fd { number: 3, path: PathBuf::from("socket:[31337]") };
+        let pipe = Fd { number: 4, path: PathBuf::from("pipe:[31338]") };
+        let file = Fd { number: 5, path: PathBuf::from("/tmp/socket:[1]") };
+        assert_eq!(socket.socket_inode(), Some(31337));
+        assert_eq!(pipe.socket_inode(), None);
+        assert_eq!(file.socket_inode(), None);
+    }
+
+    #[test]
+    fn process_key_roundtrip() {
+        let key = ProcessKey {
+            boot_id: "0c7d9a3e-5f4b-4b8e-9b0a-2f1e6d3c8a71".to_string(),
+            pid: 1234,
+            starttime_ticks: 1500000,
+        };
+        assert_eq!(key.to_string(), "0c7d9a3e-5f4b-4b8e-9b0a-2f1e6d3c8a71:1234:1500000");
+        assert_eq!(ProcessKey::from_str(&key.to_string()).unwrap(), key);
+    }
+
+    #[test]
+    fn process_key_error() {
+        assert!(ProcessKey::from_str("1234:1500000").is_err());
+        assert!(ProcessKey::from_str(":1234:1500000").is_err());
+        assert!(ProcessKey::from_str("0c7d9a3e:1234:").is_err());
+        assert!(ProcessKey::from_str("0c7d9a3e:pid:1500000").is_err());
+    }
+
+    const STATUS: &str = "Name:\tnginx\nUmask:\t0022\nState:\tS (sleeping)\nTgid:\t1205\n\
+        Ngid:\t0\nPid:\t1205\nPPid:\t1\nTracerPid:\t0\nUid:\t33\t0\t0\t0\nGid:\t33\t0\t0\t0\n\
+        FDSize:\t64\nGroups:\t4 24 27 \nVmPeak:\t  125632 kB\nVmSize:\t  125600 kB\nVmLck:\t       0 kB\n\
+        VmHWM:\t   10940 kB\nVmRSS:\t   10900 kB\nVmSwap:\t     128 kB\nThreads:\t1\n\
+        SigQ:\t0/63443\nSigPnd:\t0000000000000000\nShdPnd:\t0000000000000100\n\
+        SigBlk:\t0000000000010000\nSigIgn:\t0000000000001000\nSigCgt:\t0000000180004a07\n\
+        CapInh:\t0000000000000000\nCapPrm:\t0000003fffffffff\nCapEff:\t0000000000200400\n\
+        CapBnd:\t0000003fffffffff\nCapAmb:\t0000000000000000\nNoNewPrivs:\t1\nSeccomp:\t2\n\
+        Cpus_allowed:\tf\nCpus_allowed_list:\t0-2,5\nvoluntary_ctxt_switches:\t1712\n\
+        nonvoluntary_ctxt_switches:\t31\n";
+
+    #[test]
+    fn status_parses() {
+        let s = ProcessStatus::parse(STATUS, &PathBuf::from("/proc/1205/status")).unwrap();
+        assert_eq!(s.name, "nginx");
+        assert_eq!(s.umask, Some(0o022));
+        assert_eq!(s.ppid, 1);
+        assert_eq!(s.uids, Uids { real: 33, effective: 0, saved: 0, filesystem: 0 });
+        assert_eq!(s.gids.real, 33);
+        assert_eq!(s.groups, vec![4, 24, 27]);
+        assert_eq!(s.vm_peak, Some(125632 * 1024));
+        assert_eq!(s.vm_hwm, Some(10940 * 1024));
+        assert_eq!(s.vm_swap, Some(128 * 1024));
+        assert_eq!(s.shd_pnd.signals(), vec![9]);
+        assert!(s.sig_blk.contains(17));
+        assert_eq!(s.sig_cgt.signals(), vec![1, 2, 3, 10, 12, 15, 32, 33]);
+        assert!(s.cap_eff.contains(10));
+        assert!(s.cap_eff.contains(21));
+        assert_eq!(s.cap_eff.capabilities(), vec![10, 21]);
+        assert_eq!(s.cap_prm.capabilities().len(), 38);
+        assert_eq!(s.cap_amb, Some(CapabilitySet(0)));
+        assert_eq!(s.no_new_privs, Some(true));
+        assert_eq!(s.seccomp, Some(SeccompMode::Filter));
+        assert_eq!(s.cpus_allowed_list, vec![0, 1, 2, 5]);
+        assert_eq!(s.voluntary_ctxt_switches, 1712);
+        assert_eq!(s.nonvoluntary_ctxt_switches, 31);
+    }
+
+    #[test]
+    fn status_kernel_thread() {
+        // Kernel threads have no memory fields, and old kernels lack the newer fields
+        let status = STATUS.lines()
+            .filter(|line| !line.starts_with("Vm") && !line.starts_with("CapAmb") &&
+                           !line.starts_with("NoNewPrivs") && !line.starts_with("Seccomp") &&
+                           !line.starts_with("Umask"))
+            .collect::<Vec<&str>>()
+            .join("\n");
+        let s = ProcessStatus::parse(&status, &PathBuf::from("/proc/2/status")).unwrap();
+        assert_eq!(s.vm_peak, None);
+        assert_eq!(s.umask, None);
+        assert_eq!(s.cap_amb, None);
+        assert_eq!(s.no_new_privs, None);
+        assert_eq!(s.seccomp, None);
+    }
+
+    #[test]
+    fn status_error() {
+        let status = STATUS.replace("Uid:\t33\t0\t0\t0", "Uid:\t33\t0");
+        assert!(ProcessStatus::parse(&status, &PathBuf::from("/proc/1205/status")).is_err());
+        let status = STATUS.replace("Pid:\t1205\nPPid", "PPid");
+        assert!(ProcessStatus::parse(&status, &PathBuf::from("/proc/1205/status")).is_err());
+    }
+
+    #[test]
+    fn status_parse_list() {
+        assert_eq!(ProcessStatus::parse_list("0-3,8,10-11").unwrap(), vec![0, 1, 2, 3, 8, 10, 11]);
+        assert_eq!(ProcessStatus::parse_list("").unwrap(), vec![]);
+        assert_eq!(ProcessStatus::parse_list("3-1").unwrap_err().kind(), ErrorKind::InvalidData);
+        assert!(ProcessStatus::parse_list("0-x").is_err());
+        assert!(ProcessStatus::parse_list("-3").is_err());
+    }
+
+    #[test]
+    fn status_real_ids() {
+        let path = PathBuf::from("/proc/1205/status");
+        assert_eq!(ProcessStatus::parse_real_ids(STATUS, &path).unwrap(), (33, 33));
+
+        // Only the IDs are needed, as sandboxes such as gVisor omit some other fields
+        let status = "Name:\tnginx\nUid:\t1000\t1000\t1000\t1000\nGid:\t100\t100\t100\t100\n";
+        assert!(ProcessStatus::parse(status, &path).is_err());
+        assert_eq!(ProcessStatus::parse_real_ids(status, &path).unwrap(), (1000, 100));
+
+        assert!(ProcessStatus::parse_real_ids("Name:\tnginx\nUid:\t1000\n", &path).is_err());
+        assert!(ProcessStatus::parse_real_ids("Uid:\tx\nGid:\t100\n", &path).is_err());
+    }
+
+    #[test]
+    fn signal_set_bounds() {
+        let set = SignalSet(u64::MAX);
+        assert!(!set.contains(0));
+        assert!(set.contains(64));
+        assert!(!set.contains(65));
+        assert_eq!(set.signals().len(), 64);
+    }
+
+    #[test]
+    fn memory_full_info_rollup() {
+        let smaps = b"56282b2aa000-7ffec883c000 ---p 00000000 00:00 0                          [rollup]\n\
+                     Rss:                1304 kB\nPss:                 468 kB\nPss_Dirty:           104 kB\n\
+                     Shared_Clean:       1132 kB\nShared_Dirty:          0 kB\nPrivate_Clean:        68 kB\n\
+                     Private_Dirty:       104 kB\nReferenced:         1304 kB\nAnonymous:           104 kB\n\
+                     Swap:                 16 kB\nSwapPss:               8 kB\nLocked:                0 kB\n";
+        let m = MemoryFullInfo::parse(smaps, &PathBuf::from("/proc/1/smaps_rollup")).unwrap();
+        assert_eq!(m.rss, 1304 * 1024);
+        assert_eq!(m.pss, 468 * 1024);
+        assert_eq!(m.uss, (68 + 104) * 1024);
+        assert_eq!(m.swap, 16 * 1024);
+        assert_eq!(m.shared_clean, 1132 * 1024);
+        assert_eq!(m.private_dirty, 104 * 1024);
+    }
+
+    #[test]
+    fn memory_full_info_smaps() {
+        let smaps = b"55ca159eb000-55ca159ee000 r--p 00000000 fe:00 318204                     /usr/bin/sed\n\
+                     Size:                 12 kB\nRss:                  12 kB\nPss:                  12 kB\n\
+                     Private_Clean:        12 kB\nTHPeligible:           0\nVmFlags: rd mr mw me sd\n\
+                     7f2c1a000000-7f2c1a021000 rw-p 00000000 00:00 0\n\
+                     Size:                132 kB\nRss:                   8 kB\nPss:                   4 kB\n\
+                     Shared_Dirty:          4 kB\nPrivate_Dirty:         4 kB\nVmFlags: rd wr mr mw me ac sd\n";
+        let m = MemoryFullInfo::parse(smaps, &PathBuf::from("/proc/1/smaps")).unwrap();
+        assert_eq!(m.rss, 20 * 1024);
+        assert_eq!(m.pss, 16 * 1024);
+        assert_eq!(m.uss, 16 * 1024);
+        assert_eq!(m.shared_dirty, 4 * 1024);
+        assert_eq!(m.swap, 0);
+    }
+
+    #[test]
+    fn memory_full_info_error() {
+        let smaps = b"Rss:                lots kB\n";
+        assert!(MemoryFullInfo::parse(smaps, &PathBuf::from("/proc/1/smaps")).is_err());
+    }
+
+    const SMAPS: &[u8] = b"55ca159eb000-55ca159ee000 r--p 00000000 fe:00 318204                     /usr/bin/sed\n\
+        Size:                 12 kB\nRss:                  12 kB\nPss:                   6 kB\n\
+        Shared_Clean:          8 kB\nPrivate_Clean:         4 kB\nReferenced:           12 kB\n\
+        VmFlags: rd mr mw me sd\n\
+        55ca159ee000-55ca15a0c000 r-xp 00003000 fe:00 318204                     /usr/bin/sed\n\
+        Size:                120 kB\nRss:                 80 kB\nPss:                  40 kB\n\
+        Shared_Clean:         80 kB\n\
+        7f2c1a000000-7f2c1a021000 rw-p 00000000 00:00 0 \n\
+        Size:                132 kB\nRss:                   8 kB\nPrivate_Dirty:         8 kB\n\
+        Anonymous:             8 kB\nSwap:                  4 kB\n\
+        7ffd4a5f9000-7ffd4a61a000 rw-p 00000000 00:00 0                          [stack]\n\
+        Size:                132 kB\nRss:                  16 kB\n\
+        7f2c1a100000-7f2c1a101000 rw-s 00000000 00:05 1234                       /dev/shm/my file (deleted)\n\
+        Size:                  4 kB\n";
+
+    #[test]
+    fn memory_maps_parses() {
+        let maps = MemoryMap::parse(SMAPS, &PathBuf::from("/proc/1/smaps")).unwrap();
+        assert_eq!(maps.len(), 5);
+
+        assert_eq!(maps[1].path, Path::new("/usr/bin/sed"));
+        assert_eq!(maps[1].region, Some(MapRegion {
+            start: 0x55ca159ee000,
+            end: 0x55ca15a0c000,
+            perms: "r-xp".to_string(),
+            offset: 0x3000,
+            dev_major: 0xfe,
+            dev_minor: 0,
+            inode: 318204,
+        }));
+        assert_eq!(maps[1].rss, 80 * 1024);
+        assert_eq!(maps[1].pss, 40 * 1024);
+
+        assert_eq!(maps[2].path, Path::new("[anon]"));
+        assert_eq!(maps[2].anonymous, 8 * 1024);
+        assert_eq!(maps[2].swap, 4 * 1024);
+        assert_eq!(maps[3].path, Path::new("[stack]"));
+        assert_eq!(maps[4].path, Path::new("/dev/shm/my file (deleted)"));
+
+        let smaps = b"7f2c1a100000-7f2c1a101000 rw-s 00000000 00:05 1234 /tmp/\xff\n";
+        let maps = MemoryMap::parse(smaps, &PathBuf::from("/proc/1/smaps")).unwrap();
+        assert_eq!(maps[0].path, Path::new(OsStr::from_bytes(b"/tmp/\xff")));
+    }
+
+    #[test]
+    fn memory_maps_grouped() {
+        let maps = MemoryMap::parse(SMAPS, &PathBuf::from("/proc/1/smaps")).unwrap();
+        let grouped = MemoryMap::group(maps);
+        assert_eq!(grouped.len(), 4);
+        assert_eq!(grouped[0].path, Path::new("/usr/bin/sed"));
+        assert_eq!(grouped[0].region, None);
+        assert_eq!(grouped[0].size, 132 * 1024);
+        assert_eq!(grouped[0].rss, 92 * 1024);
+        assert_eq!(grouped[0].pss, 46 * 1024);
+        assert_eq!(grouped[0].private_clean, 4 * 1024);
+        assert_eq!(grouped[1].path, Path::new("[anon]"));
+    }
+
+    #[test]
+    fn memory_maps_error() {
+        let path = PathBuf::from("/proc/1/smaps");
+        assert!(MemoryMap::parse(b"Rss:                  12 kB\n", &path).is_err());
+        assert!(MemoryMap::parse(b"55ca159eb000 r--p 00000000 fe:00 318204 /usr/bin/sed\n", &path).is_err());
+    }
+
+    #[test]
+    fn io_counters_parses() {
+        let io = "rchar: 323934931\nwchar: 323929600\nsyscr: 632687\nsyscw: 632675\n\
+                  read_bytes: 4096\nwrite_bytes: 323932160\ncancelled_write_bytes: 512\n";
+        let c = IoCounters::parse(io, &PathBuf::from("/proc/1/io")).unwrap();
+        assert_eq!(c.rchar, 323934931);
+        assert_eq!(c.wchar, 323929600);
+        assert_eq!(c.syscr, 632687);
+        assert_eq!(c.syscw, 632675);
+        assert_eq!(c.read_bytes, 4096);
+        assert_eq!(c.write_bytes, 323932160);
+        assert_eq!(c.cancelled_write_bytes, 512);
+    }
+
+    #[test]
+    fn io_counters_error() {
+        let path = PathBuf::from("/proc/1/io");
+        assert!(IoCounters::parse("rchar: 1\nwchar: 2\n", &path).is_err());
+        assert!(IoCounters::parse("rchar: lots\n", &path).is_err());
+    }
+
+    fn fake_process(pid: PID, ppid: PID, comm: &str) -> Process {
+        let stat = format!("{} ({}) S {} 1 1 0 -1 4219136 48162 38210015093 1033 16767427 1781 2205 119189638 18012864 20 0 1 0 9 34451456 504 18446744073709551615 1 1 0 0 0 0 0 4096 536962595 0 0 0 17 0 0 0 189 0 0 0 0 0 0 0 0 0 0\n", pid, comm, ppid);
+        Process::new_internal(&stat, 0, 0, &PathBuf::from("/proc/1/stat")).unwrap()
+    }
+
+    fn fake_tree() -> ProcessTree {
+        ProcessTree::from_processes(vec![
+            fake_process(1, 0, "init"),
+            fake_process(2, 0, "kthreadd"),
+            fake_process(600, 1, "cron"),
+            fake_process(512, 1, "sshd"),
+            fake_process(1024, 512, "bash"),
+            fake_process(1025, 1024, "vim"),
+            fake_process(3, 2, "kworker/0:0"),
+        ])
+    }
+
+    #[test]
+    fn process_tree_navigation() {
+        let tree = fake_tree();
+        let pids = |processes: Vec<&Process>| processes.iter().map(|p| p.pid).collect::<Vec<PID>>();
+
+        assert_eq!(pids(tree.roots()), vec![1, 2]);
+        assert_eq!(pids(tree.children(1)), vec![512, 600]);
+        assert_eq!(pids(tree.descendants(1)), vec![512, 1024, 1025, 600]);
+        assert_eq!(pids(tree.descendants(1025)), Vec::<PID>::new());
+        assert_eq!(tree.parent(1024).map(|p| p.pid), Some(512));
+        assert!(tree.parent(1).is_none());
+        assert!(tree.get(4).is_none());
+
+        let mut walked = Vec::new();
+        tree.walk(|process, depth| walked.push((process.pid, depth)));
+        assert_eq!(walked, vec![(1, 0), (512, 1), (1024, 2), (1025, 3), (600, 1), (2, 0), (3, 1)]);
+    }
+
+    #[test]
+    fn process_tree_cycle() {
+        // PID reuse while reading `/proc` can make processes appear to be each other's parent
+        let tree = ProcessTree::from_processes(vec![
+            fake_process(1, 0, "init"),
+            fake_process(10, 11, "a"),
+            fake_process(11, 10, "b"),
+            fake_process(12, 11, "c"),
+            fake_process(20, 20, "self"),
+        ]);
+        let pids = |processes: Vec<&Process>| processes.iter().map(|p| p.pid).collect::<Vec<PID>>();
+
+        assert_eq!(pids(tree.descendants(10)), vec![11, 12]);
+        assert_eq!(pids(tree.descendants(20)), Vec::<PID>::new());
+        assert_eq!(tree.to_string(), "1 init\n\
+                                      10 a\n\
+                                      └─ 11 b\n   \
+                                      └─ 12 c\n\
+                                      20 self\n");
+
+        let mut walked = Vec::new();
+        tree.walk(|process, depth| walked.push((process.pid, depth)));
+        assert_eq!(walked, vec![(1, 0), (10, 0), (11, 1), (12, 2), (20, 0)]);
+    }
+
+    #[test]
+    fn process_tree_display() {
+        assert_eq!(fake_tree().to_string(), "1 init\n\
+                                             ├─ 512 sshd\n\
+                                             │  └─ 1024 bash\n\
+                                             │     └─ 1025 vim\n\
+                                             └─ 600 cron\n\
+                                             2 kthreadd\n\
+                                             └─ 3 kworker/0:0\n");
+    }
+
+    #[test]
+    fn signal_from_raw() {
+        assert_eq!(Signal::from_raw(libc::SIGTERM).unwrap(), Signal::Term);
+        assert_eq!(Signal::from_raw(9).unwrap(), Signal::Kill);
+        assert!(Signal::from_raw(0).is_err());
+    }
+
+    #[test]
+    fn send_signal_checks_identity() {
+        // Same PID as the test process, but a different start time
+        let process = fake_process(::getpid(), 1, "impostor");
+        let error = process.send_signal(Signal::Term).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::NotFound);
+    }
+
+    #[test]
+    fn wait_status() {
+        assert_eq!(decode_wait_status(0), Some(0));
+        assert_eq!(decode_wait_status(3 << 8), Some(3));
+        assert_eq!(decode_wait_status(libc::SIGKILL), Some(-9));
+        // Stopped rather than exited
+        assert_eq!(decode_wait_status(0x137f), None);
+    }
+
+    #[test]
+    fn ionice_raw() {
+        let ionice = IoNice { class: IoClass::BestEffort, value: 4 };
+        assert_eq!(ionice.to_raw().unwrap(), 2 << 13 | 4);
+        assert_eq!(IoNice::from_raw(2 << 13 | 4).unwrap(), ionice);
+        // Hint bits above the level are ignored
+        assert_eq!(IoNice::from_raw(2 << 13 | 1 << 3 | 4).unwrap(), ionice);
+        assert_eq!(IoNice::from_raw(0).unwrap(), IoNice { class: IoClass::None, value: 0 });
+
+        assert!(IoNice { class: IoClass::Idle, value: 1 }.to_raw().is_err());
+        assert!(IoNice { class: IoClass::RealTime, value: 8 }.to_raw().is_err());
+    }
+
+    #[test]
+    fn process_flags() {
+        let process = fake_process(1, 0, "init");
+        assert_eq!(process.flags(), ProcessFlags(0x406100));
+        assert!(process.flags().contains(ProcessFlags::SUPERPRIV));
+        assert!(!process.flags().contains(ProcessFlags::FORKNOEXEC));
+        assert!(!process.is_kernel_thread());
+        assert_eq!(process.policy(), SchedPolicy::Normal);
+
+        assert!("abc".parse::<ProcessFlags>().is_err());
+        assert_eq!("6".parse::<SchedPolicy>().unwrap(), SchedPolicy::Deadline);
+        assert_eq!("7".parse::<SchedPolicy>().unwrap(), SchedPolicy::Other(7));
+        assert!(SchedPolicy::Fifo.is_realtime());
+    }
+
+    #[test]
+    fn process_flags_kernel_thread() {
+        let stat = "2 (kthreadd) S 0 0 0 0 -1 2129984 0 0 0 0 0 2 0 0 20 0 1 0 9 0 0 18446744073709551615 0 0 0 0 0 0 0 2147483647 0 0 0 0 0 1 0 0 0 0 0 0 0 0 0 0 0 0 0\n";
+        let process = Process::new_internal(stat, 0, 0, &PathBuf::from("/proc/2/stat")).unwrap();
+        assert_eq!(process.flags, 0x208040);
+        assert!(process.flags().contains(ProcessFlags::KTHREAD));
+        assert!(process.flags().contains(ProcessFlags::NOFREEZE));
+        assert!(process.is_kernel_thread());
+    }
+
+    #[test]
+    fn terminal_decoding() {
+        assert_eq!(Terminal::from_tty_nr(0), None);
+        assert_eq!(Terminal::from_tty_nr(34819), Some(Terminal { major: 136, minor: 3 }));
+        assert_eq!(Terminal::from_tty_nr(136 << 8 | 300 & 0xff | (300 & !0xff) << 12),
+                   Some(Terminal { major: 136, minor: 300 }));
+    }
+
+    #[test]
+    fn terminal_path() {
+        let drivers = "/dev/tty             /dev/tty        5       0 system:/dev/tty
+/dev/console         /dev/console    5       1 system:console
+serial               /dev/ttyS       4 64-95 serial
+pty_slave            /dev/pts      136 0-1048575 pty:slave
+unknown              /dev/tty        4 1-63 console
+";
+        let path = |major, minor| Terminal { major, minor }.path_from_drivers(drivers);
+        assert_eq!(path(136, 3), Some(PathBuf::from("/dev/pts/3")));
+        assert_eq!(path(5, 1), Some(PathBuf::from("/dev/console")));
+        assert_eq!(path(200, 0), None);
+    }
+
+    #[test]
+    fn cmdline_split() {
+        let args = |cmdline: &[u8]| -> Vec<String> {
+            Process::cmdline_internal(cmdline)
+                .iter()
+                .map(|arg| arg.to_string_lossy().into_owned())
+                .collect()
+        };
+
+        assert_eq!(args(b"sh\0-c\0echo 'my job'\0"), vec!["sh", "-c", "echo 'my job'"]);
+        assert_eq!(args(b"a\0\0b\0"), vec!["a", "", "b"]);
+        // Processes that rewrite their argv
+        assert_eq!(args(b"nginx: worker process"), vec!["nginx:", "worker", "process"]);
+        assert_eq!(args(b"postgres: writer process\0"), vec!["postgres:", "writer", "process"]);
+
+        let cmdline = Process::cmdline_internal(b"cat\0\xff\0");
+        assert_eq!(cmdline[1].as_bytes(), b"\xff");
+    }
+
+    #[test]
+    fn shell_quoting() {
+        assert_eq!(shell_quote("--name=x/y.txt"), "--name=x/y.txt");
+        assert_eq!(shell_quote("my job"), "'my job'");
+        assert_eq!(shell_quote(""), "''");
+        assert_eq!(shell_quote("it's"), "'it'\"'\"'s'");
+    }
+
     #[test]
     fn environ() {
         let fc = "HOME=/\0init=/sbin/init\0recovery=\0TERM=linux\0BOOT_IMAGE=/boot/vmlinuz-3.13.0-128-generic\0PATH=/sbin:/usr/sbin:/bin:/usr/bin\0PWD=/\0rootmnt=/root\0";
@@ -649,4 +3099,16 @@ mod unit_tests {
         assert_eq!(e["rootmnt"], "/root");
         assert_eq!(e["recovery"], "");
     }
+
+    #[test]
+    fn environ_os() {
+        let e = Environ::parse(b"PATH=/bin\0junk\0LANG=C\0NAME=\xff\0PATH=/usr/bin\0A=b=c\0");
+        let names: Vec<_> = e.vars.iter().map(|(name, _)| name.to_str().unwrap()).collect();
+        assert_eq!(names, vec!["PATH", "LANG", "NAME", "PATH", "A"]);
+        assert_eq!(e.malformed, vec![OsString::from("junk")]);
+        assert_eq!(e.get("PATH"), Some(OsStr::new("/usr/bin")));
+        assert_eq!(e.get("NAME").unwrap().as_bytes(), b"\xff");
+        assert_eq!(e.get("A"), Some(OsStr::new("b=c")));
+        assert_eq!(e.get("HOME"), None);
+    }
 }
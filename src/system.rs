@@ -1,13 +1,25 @@
 //! Read information about the operating system from `/proc`.
 
 use std::str::FromStr;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::collections::HashMap;
+use std::ffi::{CStr, CString, OsString};
+use std::fmt;
+use std::fs::{self, read_dir};
+use std::mem;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::os::unix::ffi::{OsStrExt, OsStringExt};
+use std::ptr;
+use std::thread;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
 use std::io::{Result, ErrorKind, Error};
 
+use libc::{self, _SC_NPROCESSORS_ONLN, sysconf};
+
 use PID;
 
+use process::TICKS_PER_SECOND;
 use utils::read_file;
 
 #[derive(Debug)]
@@ -128,6 +140,655 @@ pub struct LoadAverage {
     pub last_pid: PID,
 }
 
+/// Time spent by the CPU in each mode, read from `/proc/stat`.
+///
+/// All fields are in seconds. On Linux `guest` and `guest_nice` are also included in `user` and
+/// `nice` respectively. Kernels older than 2.6.24 do not report `guest`, and kernels older than
+/// 2.6.33 do not report `guest_nice`; these fields are set to 0 when they are missing.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct CpuTimes {
+    /// Time spent by normal processes executing in user mode
+    pub user: f64,
+
+    /// Time spent by niced processes executing in user mode
+    pub nice: f64,
+
+    /// Time spent by processes executing in kernel mode
+    pub system: f64,
+
+    /// Time spent doing nothing
+    pub idle: f64,
+
+    /// Time spent waiting for I/O to complete
+    pub iowait: f64,
+
+    /// Time spent servicing hardware interrupts
+    pub irq: f64,
+
+    /// Time spent servicing software interrupts
+    pub softirq: f64,
+
+    /// Time spent by other operating systems running in a virtualized environment
+    pub steal: f64,
+
+    /// Time spent running a virtual CPU for guest operating systems
+    pub guest: f64,
+
+    /// Time spent running a niced guest
+    pub guest_nice: f64,
+}
+
+impl CpuTimes {
+    /// Parses a `cpu` or `cpuN` line from `/proc/stat`, converting clock ticks to seconds.
+    fn from_line(line: &str) -> Result<CpuTimes> {
+        let fields: Vec<&str> = line.split_whitespace().skip(1).collect();
+
+        if fields.len() < 8 {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                format!("Expected at least 8 CPU time fields, got {}", fields.len()),
+            ));
+        }
+
+        let mut values = [0.0; 10];
+        for (value, field) in values.iter_mut().zip(fields.iter()) {
+            *value = try_parse!(*field, u64::from_str) as f64 / *TICKS_PER_SECOND;
+        }
+
+        Ok(CpuTimes {
+            user: values[0],
+            nice: values[1],
+            system: values[2],
+            idle: values[3],
+            iowait: values[4],
+            irq: values[5],
+            softirq: values[6],
+            steal: values[7],
+            guest: values[8],
+            guest_nice: values[9],
+        })
+    }
+
+    /// Total time, excluding `guest` and `guest_nice` which are already counted in `user` and
+    /// `nice`.
+    fn total(&self) -> f64 {
+        self.user + self.nice + self.system + self.idle + self.iowait + self.irq + self.softirq +
+            self.steal
+    }
+
+    /// Time spent doing work, i.e. not idle or waiting for I/O.
+    fn busy(&self) -> f64 {
+        self.total() - self.idle - self.iowait
+    }
+}
+
+/// Percentage of time spent by the CPU in each mode over an interval.
+///
+/// See `CpuTimes` for a description of each field.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct CpuTimesPercent {
+    pub user: f32,
+    pub nice: f32,
+    pub system: f32,
+    pub idle: f32,
+    pub iowait: f32,
+    pub irq: f32,
+    pub softirq: f32,
+    pub steal: f32,
+    pub guest: f32,
+    pub guest_nice: f32,
+}
+
+impl CpuTimesPercent {
+    fn new(before: &CpuTimes, after: &CpuTimes) -> CpuTimesPercent {
+        let total = after.total() - before.total();
+        let percent = |before: f64, after: f64| percent_of(after - before, total);
+
+        CpuTimesPercent {
+            user: percent(before.user, after.user),
+            nice: percent(before.nice, after.nice),
+            system: percent(before.system, after.system),
+            idle: percent(before.idle, after.idle),
+            iowait: percent(before.iowait, after.iowait),
+            irq: percent(before.irq, after.irq),
+            softirq: percent(before.softirq, after.softirq),
+            steal: percent(before.steal, after.steal),
+            guest: percent(before.guest, after.guest),
+            guest_nice: percent(before.guest_nice, after.guest_nice),
+        }
+    }
+}
+
+/// Returns `part` as a percentage of `total`, clamped to the range 0..100.
+///
+/// Counters in `/proc/stat` can occasionally go backwards, so the result is clamped rather than
+/// trusted blindly. An empty interval gives 0.
+fn percent_of(part: f64, total: f64) -> f32 {
+    if total <= 0.0 {
+        return 0.0;
+    }
+    ((part / total * 100.0) as f32).clamp(0.0, 100.0)
+}
+
+/// Percentage of time the CPU was busy between two samples.
+fn busy_percent(before: &CpuTimes, after: &CpuTimes) -> f32 {
+    percent_of(after.busy() - before.busy(), after.total() - before.total())
+}
+
+/// Calculates CPU utilisation without blocking, by remembering the CPU times from the last call.
+///
+/// Each method reads `/proc/stat` and compares it with the sample taken by the previous call to
+/// any method (or by `new()`), so the first result covers the time since the collector was
+/// created.
+///
+/// # Examples
+///
+/// ```
+/// let mut collector = psutil::system::CpuPercentCollector::new().unwrap();
+/// std::thread::sleep(std::time::Duration::from_millis(100));
+/// let percent = collector.cpu_percent().unwrap();
+/// assert!((0.0..=100.0).contains(&percent));
+/// ```
+#[derive(Clone, Debug)]
+pub struct CpuPercentCollector {
+    cpu_times: CpuTimes,
+    cpu_times_percpu: Vec<CpuTimes>,
+}
+
+impl CpuPercentCollector {
+    /// Creates a collector, taking an initial sample of `/proc/stat`.
+    pub fn new() -> Result<CpuPercentCollector> {
+        let data = read_file(Path::new("/proc/stat"))?;
+        let (cpu_times, cpu_times_percpu) = cpu_times_internal(&data)?;
+
+        Ok(CpuPercentCollector {
+            cpu_times,
+            cpu_times_percpu,
+        })
+    }
+
+    /// Takes a new sample, returning the previous one.
+    fn update(&mut self) -> Result<CpuPercentCollector> {
+        let next = CpuPercentCollector::new()?;
+        Ok(::std::mem::replace(self, next))
+    }
+
+    /// Returns the system-wide CPU utilisation as a percentage since the last call.
+    pub fn cpu_percent(&mut self) -> Result<f32> {
+        let before = self.update()?;
+        Ok(busy_percent(&before.cpu_times, &self.cpu_times))
+    }
+
+    /// Returns the utilisation of each CPU as a percentage since the last call.
+    pub fn cpu_percent_percpu(&mut self) -> Result<Vec<f32>> {
+        let before = self.update()?;
+        Ok(before
+            .cpu_times_percpu
+            .iter()
+            .zip(self.cpu_times_percpu.iter())
+            .map(|(before, after)| busy_percent(before, after))
+            .collect())
+    }
+
+    /// Returns the system-wide percentage of time spent in each mode since the last call.
+    pub fn cpu_times_percent(&mut self) -> Result<CpuTimesPercent> {
+        let before = self.update()?;
+        Ok(CpuTimesPercent::new(&before.cpu_times, &self.cpu_times))
+    }
+
+    /// Returns the percentage of time spent in each mode for each CPU since the last call.
+    pub fn cpu_times_percent_percpu(&mut self) -> Result<Vec<CpuTimesPercent>> {
+        let before = self.update()?;
+        Ok(before
+            .cpu_times_percpu
+            .iter()
+            .zip(self.cpu_times_percpu.iter())
+            .map(|(before, after)| CpuTimesPercent::new(before, after))
+            .collect())
+    }
+}
+
+/// Disk I/O statistics, read from `/proc/diskstats`.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct DiskIOCounters {
+    /// Number of reads completed
+    pub read_count: u64,
+
+    /// Number of writes completed
+    pub write_count: u64,
+
+    /// Number of bytes read
+    pub read_bytes: u64,
+
+    /// Number of bytes written
+    pub write_bytes: u64,
+
+    /// Time spent reading (milliseconds)
+    pub read_time: u64,
+
+    /// Time spent writing (milliseconds)
+    pub write_time: u64,
+
+    /// Number of reads merged with adjacent reads
+    pub read_merged_count: u64,
+
+    /// Number of writes merged with adjacent writes
+    pub write_merged_count: u64,
+
+    /// Time spent doing I/O (milliseconds)
+    pub busy_time: u64,
+}
+
+/// Size of the sectors counted in `/proc/diskstats`.
+///
+/// The kernel always counts 512 byte sectors, whatever the device's `queue/hw_sector_size` is, so
+/// scaling by the hardware sector size would overstate the bytes transferred by 4Kn disks 8 times.
+const SECTOR_SIZE: u64 = 512;
+
+impl DiskIOCounters {
+    /// Parses the fields of a `/proc/diskstats` line following the device name.
+    ///
+    /// Kernels before 2.6.25 only report 4 fields for partitions.
+    fn from_fields(fields: &[&str]) -> Result<DiskIOCounters> {
+        let mut values = Vec::with_capacity(fields.len());
+        for field in fields {
+            values.push(try_parse!(*field, u64::from_str));
+        }
+
+        match values.len() {
+            4 => Ok(DiskIOCounters {
+                read_count: values[0],
+                read_bytes: values[1] * SECTOR_SIZE,
+                write_count: values[2],
+                write_bytes: values[3] * SECTOR_SIZE,
+                ..DiskIOCounters::default()
+            }),
+            n if n >= 11 => Ok(DiskIOCounters {
+                read_count: values[0],
+                read_merged_count: values[1],
+                read_bytes: values[2] * SECTOR_SIZE,
+                read_time: values[3],
+                write_count: values[4],
+                write_merged_count: values[5],
+                write_bytes: values[6] * SECTOR_SIZE,
+                write_time: values[7],
+                busy_time: values[9],
+            }),
+            n => Err(Error::new(
+                ErrorKind::InvalidData,
+                format!("Expected 4 or at least 11 disk stat fields, got {}", n),
+            )),
+        }
+    }
+
+    fn add(&mut self, other: &DiskIOCounters) {
+        self.read_count += other.read_count;
+        self.write_count += other.write_count;
+        self.read_bytes += other.read_bytes;
+        self.write_bytes += other.write_bytes;
+        self.read_time += other.read_time;
+        self.write_time += other.write_time;
+        self.read_merged_count += other.read_merged_count;
+        self.write_merged_count += other.write_merged_count;
+        self.busy_time += other.busy_time;
+    }
+}
+
+/// A mounted filesystem, read from `/proc/self/mountinfo`.
+#[derive(Clone, Debug)]
+pub struct DiskPartition {
+    /// Device the filesystem is mounted from (e.g. `/dev/sda1`)
+    pub device: String,
+
+    /// Path the filesystem is mounted at
+    pub mountpoint: PathBuf,
+
+    /// Filesystem type (e.g. `ext4`)
+    pub fstype: String,
+
+    /// Comma-separated mount options (e.g. `rw,relatime`)
+    pub opts: String,
+}
+
+#[derive(Debug)]
+pub struct DiskUsage {
+    /// Total size of the filesystem in bytes
+    pub total: u64,
+
+    /// Number of bytes used
+    pub used: u64,
+
+    /// Number of bytes available to unprivileged users
+    pub free: u64,
+
+    /// Percent of the filesystem used
+    pub percent: f32,
+}
+
+impl DiskUsage {
+    pub fn new(total: u64, used: u64, free: u64) -> DiskUsage {
+        // Like df, the percentage is relative to the space available to unprivileged users,
+        // excluding any blocks reserved for root.
+        let available = used + free;
+        let percent = if available > 0 {
+            (used as f32 / available as f32) * 100.0
+        } else {
+            0.0
+        };
+
+        DiskUsage {
+            total,
+            used,
+            free,
+            percent,
+        }
+    }
+}
+
+/// Network I/O statistics, read from `/proc/net/dev`.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct NetIOCounters {
+    /// Number of bytes sent
+    pub bytes_sent: u64,
+
+    /// Number of bytes received
+    pub bytes_recv: u64,
+
+    /// Number of packets sent
+    pub packets_sent: u64,
+
+    /// Number of packets received
+    pub packets_recv: u64,
+
+    /// Number of errors while receiving
+    pub errin: u64,
+
+    /// Number of errors while sending
+    pub errout: u64,
+
+    /// Number of incoming packets dropped
+    pub dropin: u64,
+
+    /// Number of outgoing packets dropped
+    pub dropout: u64,
+}
+
+impl NetIOCounters {
+    fn add(&mut self, other: &NetIOCounters) {
+        self.bytes_sent += other.bytes_sent;
+        self.bytes_recv += other.bytes_recv;
+        self.packets_sent += other.packets_sent;
+        self.packets_recv += other.packets_recv;
+        self.errin += other.errin;
+        self.errout += other.errout;
+        self.dropin += other.dropin;
+        self.dropout += other.dropout;
+    }
+}
+
+/// Types of socket connection that can be listed by `net_connections`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ConnectionKind {
+    /// IPv4 and IPv6
+    Inet,
+    /// IPv4
+    Inet4,
+    /// IPv6
+    Inet6,
+    /// TCP over IPv4 and IPv6
+    Tcp,
+    /// TCP over IPv4
+    Tcp4,
+    /// TCP over IPv6
+    Tcp6,
+    /// UDP over IPv4 and IPv6
+    Udp,
+    /// UDP over IPv4
+    Udp4,
+    /// UDP over IPv6
+    Udp6,
+    /// Unix domain sockets
+    Unix,
+    /// All of the above
+    All,
+}
+
+impl ConnectionKind {
+    /// Returns the names of the tables in `/proc/net` containing this kind of connection.
+    fn tables(&self) -> &'static [&'static str] {
+        match *self {
+            ConnectionKind::Inet => &["tcp", "tcp6", "udp", "udp6"],
+            ConnectionKind::Inet4 => &["tcp", "udp"],
+            ConnectionKind::Inet6 => &["tcp6", "udp6"],
+            ConnectionKind::Tcp => &["tcp", "tcp6"],
+            ConnectionKind::Tcp4 => &["tcp"],
+            ConnectionKind::Tcp6 => &["tcp6"],
+            ConnectionKind::Udp => &["udp", "udp6"],
+            ConnectionKind::Udp4 => &["udp"],
+            ConnectionKind::Udp6 => &["udp6"],
+            ConnectionKind::Unix => &["unix"],
+            ConnectionKind::All => &["tcp", "tcp6", "udp", "udp6", "unix"],
+        }
+    }
+}
+
+/// Address family of a socket.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AddressFamily {
+    Inet,
+    Inet6,
+    Unix,
+}
+
+/// Type of a socket.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SocketType {
+    Stream,
+    Datagram,
+    SeqPacket,
+}
+
+impl SocketType {
+    /// Returns a SocketType based on the `Type` column of `/proc/net/unix`.
+    fn from_unix_type(code: &str) -> Result<Self> {
+        match code {
+            "0001" => Ok(SocketType::Stream),
+            "0002" => Ok(SocketType::Datagram),
+            "0005" => Ok(SocketType::SeqPacket),
+            _ => Err(Error::new(
+                ErrorKind::InvalidData,
+                format!("Invalid socket type: {:?}", code),
+            )),
+        }
+    }
+}
+
+/// Possible states for a TCP connection.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TcpState {
+    Established,
+    SynSent,
+    SynRecv,
+    FinWait1,
+    FinWait2,
+    TimeWait,
+    Close,
+    CloseWait,
+    LastAck,
+    Listen,
+    Closing,
+    NewSynRecv,
+}
+
+impl TcpState {
+    /// Returns a TcpState based on the `st` column of `/proc/net/tcp`.
+    ///
+    /// See [tcp_states.h].
+    ///
+    /// [tcp_states.h]: https://github.com/torvalds/linux/blob/master/include/net/tcp_states.h
+    fn from_hex(state: &str) -> Result<Self> {
+        match state {
+            "01" => Ok(TcpState::Established),
+            "02" => Ok(TcpState::SynSent),
+            "03" => Ok(TcpState::SynRecv),
+            "04" => Ok(TcpState::FinWait1),
+            "05" => Ok(TcpState::FinWait2),
+            "06" => Ok(TcpState::TimeWait),
+            "07" => Ok(TcpState::Close),
+            "08" => Ok(TcpState::CloseWait),
+            "09" => Ok(TcpState::LastAck),
+            "0A" => Ok(TcpState::Listen),
+            "0B" => Ok(TcpState::Closing),
+            "0C" => Ok(TcpState::NewSynRecv),
+            _ => Err(Error::new(
+                ErrorKind::InvalidData,
+                format!("Invalid TCP state: {:?}", state),
+            )),
+        }
+    }
+}
+
+impl fmt::Display for TcpState {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match *self {
+            TcpState::Established => "ESTABLISHED",
+            TcpState::SynSent => "SYN_SENT",
+            TcpState::SynRecv => "SYN_RECV",
+            TcpState::FinWait1 => "FIN_WAIT1",
+            TcpState::FinWait2 => "FIN_WAIT2",
+            TcpState::TimeWait => "TIME_WAIT",
+            TcpState::Close => "CLOSE",
+            TcpState::CloseWait => "CLOSE_WAIT",
+            TcpState::LastAck => "LAST_ACK",
+            TcpState::Listen => "LISTEN",
+            TcpState::Closing => "CLOSING",
+            TcpState::NewSynRecv => "NEW_SYN_RECV",
+        };
+        f.write_str(name)
+    }
+}
+
+/// A socket, read from the tables in `/proc/net`.
+#[derive(Clone, Debug)]
+pub struct Connection {
+    /// File descriptor of the socket, if known
+    pub fd: Option<i32>,
+
+    /// Address family of the socket
+    pub family: AddressFamily,
+
+    /// Type of the socket
+    pub socket_type: SocketType,
+
+    /// Local address for IPv4 and IPv6 sockets
+    pub local_addr: Option<SocketAddr>,
+
+    /// Remote address for connected IPv4 and IPv6 sockets
+    pub remote_addr: Option<SocketAddr>,
+
+    /// Path for bound Unix sockets (abstract sockets start with `@`)
+    pub path: Option<PathBuf>,
+
+    /// State of TCP sockets
+    pub status: Option<TcpState>,
+
+    /// Inode of the socket, as found in `/proc/[pid]/fd`
+    pub inode: u64,
+}
+
+/// An address assigned to a network interface.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Address {
+    /// An IPv4 or IPv6 address
+    Inet(IpAddr),
+
+    /// A hardware address, such as an Ethernet MAC address
+    Link(Vec<u8>),
+}
+
+impl fmt::Display for Address {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Address::Inet(ref ip) => write!(f, "{}", ip),
+            Address::Link(ref bytes) => {
+                let octets: Vec<String> = bytes.iter().map(|b| format!("{:02x}", b)).collect();
+                f.write_str(&octets.join(":"))
+            }
+        }
+    }
+}
+
+/// Addresses of a network interface, read using `getifaddrs(3)`.
+#[derive(Clone, Debug)]
+pub struct NicAddress {
+    /// Address of the interface
+    pub address: Address,
+
+    /// Netmask for IPv4 and IPv6 addresses
+    pub netmask: Option<Address>,
+
+    /// Broadcast address, if the interface supports broadcast
+    pub broadcast: Option<Address>,
+
+    /// Destination address, if the interface is a point-to-point link
+    pub ptp: Option<Address>,
+}
+
+/// Duplex mode of a network interface.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Duplex {
+    Full,
+    Half,
+    Unknown,
+}
+
+/// Status of a network interface, read from `/sys/class/net/[name]`.
+#[derive(Clone, Copy, Debug)]
+pub struct NicStats {
+    /// Whether the interface is up
+    pub isup: bool,
+
+    /// Duplex mode of the interface
+    pub duplex: Duplex,
+
+    /// Speed of the interface in megabits per second, or 0 if it can't be determined
+    pub speed: u64,
+
+    /// Maximum transmission unit in bytes
+    pub mtu: u64,
+}
+
+/// A user logged in to the system, read from `/var/run/utmp`.
+#[derive(Clone, Debug)]
+pub struct User {
+    /// Name of the user
+    pub name: String,
+
+    /// Terminal the user is logged in on (e.g. `pts/0`)
+    pub terminal: Option<String>,
+
+    /// Remote host the user logged in from
+    pub host: Option<String>,
+
+    /// Time the user logged in
+    pub started: SystemTime,
+
+    /// PID of the login process
+    pub pid: PID,
+}
+
+/// Returns the number of logical CPUs currently online.
+pub fn cpu_count() -> Result<u64> {
+    match unsafe { sysconf(_SC_NPROCESSORS_ONLN) } {
+        -1 => Err(Error::last_os_error()),
+        count if count < 1 => Err(Error::new(
+            ErrorKind::InvalidData,
+            format!("Invalid number of CPUs: {}", count),
+        )),
+        count => Ok(count as u64),
+    }
+}
+
 /// Returns the system uptime in seconds.
 ///
 /// `/proc/uptime` contains the system uptime and idle time.
@@ -176,9 +837,685 @@ fn loadavg_internal(data: &str) -> Result<LoadAverage> {
     })
 }
 
+/// Returns the system-wide CPU times
+///
+/// `/proc/stat` contains the CPU times, summed over all CPUs
+pub fn cpu_times() -> Result<CpuTimes> {
+    let data = try!(read_file(Path::new("/proc/stat")));
+    let (total, _) = try!(cpu_times_internal(&data));
+    Ok(total)
+}
+
+/// Returns the CPU times for each CPU
+///
+/// `/proc/stat` contains the CPU times, with one `cpuN` line per CPU
+pub fn cpu_times_percpu() -> Result<Vec<CpuTimes>> {
+    let data = try!(read_file(Path::new("/proc/stat")));
+    let (_, percpu) = try!(cpu_times_internal(&data));
+    Ok(percpu)
+}
+
+/// Returns the system-wide CPU utilisation as a percentage
+///
+/// Blocks for `interval`, comparing `/proc/stat` before and after. Use `CpuPercentCollector` to
+/// avoid blocking.
+pub fn cpu_percent(interval: Duration) -> Result<f32> {
+    let mut collector = CpuPercentCollector::new()?;
+    thread::sleep(interval);
+    collector.cpu_percent()
+}
+
+/// Returns the utilisation of each CPU as a percentage
+///
+/// Blocks for `interval`, comparing `/proc/stat` before and after.
+pub fn cpu_percent_percpu(interval: Duration) -> Result<Vec<f32>> {
+    let mut collector = CpuPercentCollector::new()?;
+    thread::sleep(interval);
+    collector.cpu_percent_percpu()
+}
+
+/// Returns the system-wide percentage of time spent in each CPU mode
+///
+/// Blocks for `interval`, comparing `/proc/stat` before and after.
+pub fn cpu_times_percent(interval: Duration) -> Result<CpuTimesPercent> {
+    let mut collector = CpuPercentCollector::new()?;
+    thread::sleep(interval);
+    collector.cpu_times_percent()
+}
+
+/// Returns the percentage of time spent in each mode for each CPU
+///
+/// Blocks for `interval`, comparing `/proc/stat` before and after.
+pub fn cpu_times_percent_percpu(interval: Duration) -> Result<Vec<CpuTimesPercent>> {
+    let mut collector = CpuPercentCollector::new()?;
+    thread::sleep(interval);
+    collector.cpu_times_percent_percpu()
+}
+
+fn cpu_times_internal(data: &str) -> Result<(CpuTimes, Vec<CpuTimes>)> {
+    let mut total = None;
+    let mut percpu = Vec::new();
+
+    for line in data.lines() {
+        if line.starts_with("cpu ") {
+            total = Some(try!(CpuTimes::from_line(line)));
+        } else if line.starts_with("cpu") {
+            percpu.push(try!(CpuTimes::from_line(line)));
+        }
+    }
+
+    Ok((try!(total.ok_or(not_found("cpu"))), percpu))
+}
+
+/// Returns disk I/O statistics summed over all disks
+///
+/// `/proc/diskstats` contains the statistics. Partitions are not included, as their I/O is already
+/// counted by the disk they are on.
+pub fn disk_io_counters() -> Result<DiskIOCounters> {
+    let mut total = DiskIOCounters::default();
+    for (name, counters) in disk_io_counters_all()? {
+        if is_whole_disk(&name) {
+            total.add(&counters);
+        }
+    }
+    Ok(total)
+}
+
+/// Returns disk I/O statistics for each disk
+///
+/// `/proc/diskstats` contains the statistics. Partitions are only included if `partitions` is
+/// `true`.
+pub fn disk_io_counters_perdisk(partitions: bool) -> Result<HashMap<String, DiskIOCounters>> {
+    Ok(disk_io_counters_all()?
+        .into_iter()
+        .filter(|(name, _)| partitions || is_whole_disk(name))
+        .collect())
+}
+
+fn disk_io_counters_all() -> Result<Vec<(String, DiskIOCounters)>> {
+    let data = read_file(Path::new("/proc/diskstats"))?;
+    disk_io_counters_internal(&data)
+}
+
+fn disk_io_counters_internal(data: &str) -> Result<Vec<(String, DiskIOCounters)>> {
+    let mut disks = Vec::new();
+
+    for line in data.lines() {
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        if fields.len() < 3 {
+            continue;
+        }
+
+        let name = fields[2];
+        let counters = DiskIOCounters::from_fields(&fields[3..])?;
+        disks.push((name.to_string(), counters));
+    }
+
+    Ok(disks)
+}
+
+/// Returns `true` if the device is a whole disk rather than a partition.
+///
+/// Whole disks have a `/sys/block/[name]` entry, whereas partitions only appear beneath the disk
+/// they are on. Device names containing a `/` (e.g. `cciss/c0d0`) use a `!` in sysfs.
+fn is_whole_disk(name: &str) -> bool {
+    Path::new("/sys/block").join(name.replace('/', "!")).exists()
+}
+
+/// Returns the mounted filesystems
+///
+/// `/proc/self/mountinfo` contains the mounted filesystems. If `all` is `false`, only filesystems
+/// backed by a device are returned, i.e. pseudo filesystems such as `proc` and `tmpfs` (which
+/// `/proc/filesystems` marks as `nodev`) are skipped.
+pub fn disk_partitions(all: bool) -> Result<Vec<DiskPartition>> {
+    let data = fs::read("/proc/self/mountinfo")?;
+    let partitions = disk_partitions_internal(&data)?;
+
+    if all {
+        return Ok(partitions);
+    }
+
+    let filesystems = read_file(Path::new("/proc/filesystems"))?;
+    let physical = physical_filesystems(&filesystems);
+    Ok(partitions
+        .into_iter()
+        .filter(|p| p.device != "none" && physical.contains(&p.fstype.as_str()))
+        .collect())
+}
+
+/// Mount points are kept as raw bytes since they need not be valid UTF-8.
+fn disk_partitions_internal(data: &[u8]) -> Result<Vec<DiskPartition>> {
+    let mut partitions = Vec::new();
+
+    for line in data.split(|&b| b == b'\n').filter(|line| !line.is_empty()) {
+        // The optional fields are terminated by a single hyphen
+        let fields: Vec<&[u8]> = line.split(|&b| b == b' ').collect();
+        let separator = fields.iter().position(|&field| field == b"-");
+
+        let (mount, source) = match separator {
+            Some(i) if i >= 6 && fields.len() >= i + 3 => (&fields[..i], &fields[i + 1..]),
+            _ => {
+                return Err(Error::new(
+                    ErrorKind::InvalidData,
+                    format!(
+                        "Could not parse mountinfo line {:?}",
+                        String::from_utf8_lossy(line)
+                    ),
+                ))
+            }
+        };
+
+        partitions.push(DiskPartition {
+            device: String::from_utf8_lossy(&unescape_octal(source[1])).into_owned(),
+            mountpoint: PathBuf::from(OsString::from_vec(unescape_octal(mount[4]))),
+            fstype: String::from_utf8_lossy(source[0]).into_owned(),
+            opts: String::from_utf8_lossy(mount[5]).into_owned(),
+        });
+    }
+
+    Ok(partitions)
+}
+
+/// Returns the filesystem types that are not marked `nodev` in `/proc/filesystems`.
+fn physical_filesystems(data: &str) -> Vec<&str> {
+    data.lines()
+        .filter(|line| !line.starts_with("nodev"))
+        .map(|line| line.trim())
+        .collect()
+}
+
+/// Decodes the octal escapes (e.g. `\040` for a space) used for paths in `/proc/self/mountinfo`.
+fn unescape_octal(bytes: &[u8]) -> Vec<u8> {
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+
+    while i < bytes.len() {
+        if bytes[i] == b'\\' && i + 3 < bytes.len() {
+            let digits = ::std::str::from_utf8(&bytes[i + 1..i + 4]).unwrap_or("");
+            if let Ok(byte) = u8::from_str_radix(digits, 8) {
+                decoded.push(byte);
+                i += 4;
+                continue;
+            }
+        }
+        decoded.push(bytes[i]);
+        i += 1;
+    }
+
+    decoded
+}
+
+/// Returns disk usage statistics for the filesystem containing `path`
+///
+/// The statistics are read using `statvfs(3)`.
+pub fn disk_usage(path: &Path) -> Result<DiskUsage> {
+    let path = CString::new(path.as_os_str().as_bytes())
+        .map_err(|e| Error::new(ErrorKind::InvalidInput, e))?;
+    let mut stat: libc::statvfs = unsafe { mem::zeroed() };
+
+    if unsafe { libc::statvfs(path.as_ptr(), &mut stat) } != 0 {
+        return Err(Error::last_os_error());
+    }
+
+    let block_size = stat.f_frsize as u64;
+    let total = stat.f_blocks as u64 * block_size;
+    let free = stat.f_bfree as u64 * block_size;
+    let available = stat.f_bavail as u64 * block_size;
+
+    Ok(DiskUsage::new(total, total - free, available))
+}
+
+/// Returns network I/O statistics summed over all interfaces
+///
+/// `/proc/net/dev` contains the statistics
+pub fn net_io_counters() -> Result<NetIOCounters> {
+    let mut total = NetIOCounters::default();
+    for counters in net_io_counters_pernic()?.values() {
+        total.add(counters);
+    }
+    Ok(total)
+}
+
+/// Returns network I/O statistics for each interface
+///
+/// `/proc/net/dev` contains the statistics
+pub fn net_io_counters_pernic() -> Result<HashMap<String, NetIOCounters>> {
+    let data = read_file(Path::new("/proc/net/dev"))?;
+    Ok(net_io_counters_internal(&data)?.into_iter().collect())
+}
+
+/// Parses the table in `/proc/net/dev`.
+///
+/// The second header line names the receive and transmit columns, separated by `|`:
+///
+/// ```text
+/// Inter-|   Receive                            |  Transmit
+///  face |bytes    packets errs drop fifo frame ...|bytes    packets errs drop fifo colls ...
+///     lo: 13251901    2895    0    0    0     0 ...  13251901    2895    0    0    0     0 ...
+/// ```
+///
+/// Columns are located by name rather than position, so that additional columns don't break the
+/// parser.
+fn net_io_counters_internal(data: &str) -> Result<Vec<(String, NetIOCounters)>> {
+    let mut lines = data.lines().skip(1);
+    let header = lines.next().ok_or(not_found("/proc/net/dev header"))?;
+    let sections: Vec<&str> = header.split('|').collect();
+    if sections.len() != 3 {
+        return Err(Error::new(
+            ErrorKind::InvalidData,
+            format!("Could not parse /proc/net/dev header {:?}", header),
+        ));
+    }
+
+    let recv: Vec<&str> = sections[1].split_whitespace().collect();
+    let sent: Vec<&str> = sections[2].split_whitespace().collect();
+    let column = |columns: &[&str], offset: usize, name: &str| -> Result<usize> {
+        columns
+            .iter()
+            .position(|column| *column == name)
+            .map(|i| offset + i)
+            .ok_or(not_found(name))
+    };
+
+    let bytes_recv = column(&recv, 0, "bytes")?;
+    let packets_recv = column(&recv, 0, "packets")?;
+    let errin = column(&recv, 0, "errs")?;
+    let dropin = column(&recv, 0, "drop")?;
+    let bytes_sent = column(&sent, recv.len(), "bytes")?;
+    let packets_sent = column(&sent, recv.len(), "packets")?;
+    let errout = column(&sent, recv.len(), "errs")?;
+    let dropout = column(&sent, recv.len(), "drop")?;
+
+    let mut nics = Vec::new();
+    for line in lines {
+        // Older kernels don't put a space between the name and large byte counts
+        let colon = line.find(':').ok_or_else(|| {
+            Error::new(
+                ErrorKind::InvalidData,
+                format!("Could not parse /proc/net/dev line {:?}", line),
+            )
+        })?;
+        let (name, rest) = line.split_at(colon);
+
+        let mut values = Vec::new();
+        for field in rest[1..].split_whitespace() {
+            values.push(try_parse!(field, u64::from_str));
+        }
+        if values.len() != recv.len() + sent.len() {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                format!(
+                    "Expected {} fields for {}, got {}",
+                    recv.len() + sent.len(),
+                    name.trim(),
+                    values.len()
+                ),
+            ));
+        }
+
+        nics.push((
+            name.trim().to_string(),
+            NetIOCounters {
+                bytes_sent: values[bytes_sent],
+                bytes_recv: values[bytes_recv],
+                packets_sent: values[packets_sent],
+                packets_recv: values[packets_recv],
+                errin: values[errin],
+                errout: values[errout],
+                dropin: values[dropin],
+                dropout: values[dropout],
+            },
+        ));
+    }
+
+    Ok(nics)
+}
+
+/// Returns the system-wide socket connections
+///
+/// `/proc/net/tcp`, `/proc/net/tcp6`, `/proc/net/udp`, `/proc/net/udp6` and `/proc/net/unix`
+/// contain the connections. The `fd` field is not set, but the `inode` field can be matched
+/// against the sockets in `Process::open_fds()`.
+pub fn net_connections(kind: ConnectionKind) -> Result<Vec<Connection>> {
+    read_connections(Path::new("/proc/net"), kind)
+}
+
+/// Reads the connection tables from `dir`, which is either `/proc/net` or `/proc/[pid]/net`.
+pub(crate) fn read_connections(dir: &Path, kind: ConnectionKind) -> Result<Vec<Connection>> {
+    let mut connections = Vec::new();
+
+    for table in kind.tables() {
+        // The IPv6 tables are missing if IPv6 is disabled
+        let data = match fs::read(dir.join(table)) {
+            Ok(data) => data,
+            Err(ref e) if e.kind() == ErrorKind::NotFound => continue,
+            Err(e) => return Err(e),
+        };
+        let text = String::from_utf8_lossy(&data);
+
+        connections.extend(match *table {
+            "tcp" => parse_inet_table(&text, AddressFamily::Inet, SocketType::Stream)?,
+            "tcp6" => parse_inet_table(&text, AddressFamily::Inet6, SocketType::Stream)?,
+            "udp" => parse_inet_table(&text, AddressFamily::Inet, SocketType::Datagram)?,
+            "udp6" => parse_inet_table(&text, AddressFamily::Inet6, SocketType::Datagram)?,
+            _ => parse_unix_table(&data)?,
+        });
+    }
+
+    Ok(connections)
+}
+
+fn connection_error(line: &str) -> Error {
+    Error::new(
+        ErrorKind::InvalidData,
+        format!("Could not parse connection {:?}", line.trim()),
+    )
+}
+
+/// Parses `/proc/net/tcp`, `/proc/net/udp` or their IPv6 equivalents.
+fn parse_inet_table(
+    data: &str,
+    family: AddressFamily,
+    socket_type: SocketType,
+) -> Result<Vec<Connection>> {
+    let mut connections = Vec::new();
+
+    for line in data.lines().skip(1) {
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        if fields.len() < 10 {
+            return Err(connection_error(line));
+        }
+
+        let local_addr = parse_socket_addr(fields[1]).ok_or_else(|| connection_error(line))?;
+        let remote_addr = parse_socket_addr(fields[2]).ok_or_else(|| connection_error(line))?;
+        let status = match socket_type {
+            SocketType::Stream => Some(TcpState::from_hex(fields[3])?),
+            _ => None,
+        };
+
+        connections.push(Connection {
+            fd: None,
+            family,
+            socket_type,
+            local_addr: Some(local_addr),
+            remote_addr: if remote_addr.ip().is_unspecified() && remote_addr.port() == 0 {
+                None
+            } else {
+                Some(remote_addr)
+            },
+            path: None,
+            status,
+            inode: try_parse!(fields[9]),
+        });
+    }
+
+    Ok(connections)
+}
+
+/// Parses an address such as `0100007F:1F90` from `/proc/net/tcp`.
+///
+/// The address is printed as one (IPv4) or four (IPv6) 32 bit words in host byte order, whereas
+/// the port is printed in network byte order.
+fn parse_socket_addr(field: &str) -> Option<SocketAddr> {
+    let mut parts = field.split(':');
+    let ip = parts.next()?;
+    let port = u16::from_str_radix(parts.next()?, 16).ok()?;
+
+    let mut bytes = Vec::with_capacity(16);
+    for i in 0..ip.len() / 8 {
+        let word = u32::from_str_radix(ip.get(i * 8..i * 8 + 8)?, 16).ok()?;
+        bytes.extend_from_slice(&word.to_ne_bytes());
+    }
+
+    let ip = match bytes.len() {
+        4 => IpAddr::V4(Ipv4Addr::new(bytes[0], bytes[1], bytes[2], bytes[3])),
+        16 => {
+            let mut octets = [0; 16];
+            octets.copy_from_slice(&bytes);
+            IpAddr::V6(Ipv6Addr::from(octets))
+        }
+        _ => return None,
+    };
+
+    Some(SocketAddr::new(ip, port))
+}
+
+/// Parses `/proc/net/unix`.
+///
+/// The path is taken from the raw bytes, since socket paths need not be valid UTF-8.
+fn parse_unix_table(data: &[u8]) -> Result<Vec<Connection>> {
+    let mut connections = Vec::new();
+
+    for line in data.split(|&b| b == b'\n').skip(1).filter(|line| !line.is_empty()) {
+        let text = String::from_utf8_lossy(line);
+        let fields: Vec<&str> = text.split_whitespace().collect();
+        if fields.len() < 7 {
+            return Err(connection_error(&text));
+        }
+        let raw_fields: Vec<&[u8]> = line.split(|&b| b == b' ').filter(|f| !f.is_empty()).collect();
+
+        connections.push(Connection {
+            fd: None,
+            family: AddressFamily::Unix,
+            socket_type: SocketType::from_unix_type(fields[4])?,
+            local_addr: None,
+            remote_addr: None,
+            path: if raw_fields.len() > 7 {
+                Some(PathBuf::from(OsString::from_vec(raw_fields[7..].join(&b' '))))
+            } else {
+                None
+            },
+            status: None,
+            inode: try_parse!(fields[6]),
+        });
+    }
+
+    Ok(connections)
+}
+
+/// Returns the addresses assigned to each network interface
+///
+/// The addresses are read using `getifaddrs(3)`, and include IPv4, IPv6 and hardware addresses.
+pub fn net_if_addrs() -> Result<HashMap<String, Vec<NicAddress>>> {
+    let mut ifap: *mut libc::ifaddrs = ptr::null_mut();
+    if unsafe { libc::getifaddrs(&mut ifap) } != 0 {
+        return Err(Error::last_os_error());
+    }
+
+    let mut nics: HashMap<String, Vec<NicAddress>> = HashMap::new();
+    let mut ifa = ifap;
+    while !ifa.is_null() {
+        let entry = unsafe { &*ifa };
+        ifa = entry.ifa_next;
+
+        let address = match unsafe { sockaddr_to_address(entry.ifa_addr) } {
+            Some(address) => address,
+            None => continue,
+        };
+        let name = unsafe { CStr::from_ptr(entry.ifa_name) };
+
+        // ifa_ifu is a union of the broadcast and point-to-point destination addresses
+        let flags = entry.ifa_flags as i32;
+        let ifu = unsafe { sockaddr_to_address(entry.ifa_ifu) };
+        let (broadcast, ptp) = if flags & libc::IFF_BROADCAST != 0 {
+            (ifu, None)
+        } else if flags & libc::IFF_POINTOPOINT != 0 {
+            (None, ifu)
+        } else {
+            (None, None)
+        };
+
+        nics.entry(name.to_string_lossy().into_owned())
+            .or_default()
+            .push(NicAddress {
+                address,
+                netmask: unsafe { sockaddr_to_address(entry.ifa_netmask) },
+                broadcast,
+                ptp,
+            });
+    }
+
+    unsafe { libc::freeifaddrs(ifap) };
+    Ok(nics)
+}
+
+/// Converts an IPv4, IPv6 or packet socket address, returning `None` for other families.
+unsafe fn sockaddr_to_address(sockaddr: *const libc::sockaddr) -> Option<Address> {
+    if sockaddr.is_null() {
+        return None;
+    }
+
+    match i32::from((*sockaddr).sa_family) {
+        libc::AF_INET => {
+            let sockaddr = &*(sockaddr as *const libc::sockaddr_in);
+            let ip = Ipv4Addr::from(u32::from_be(sockaddr.sin_addr.s_addr));
+            Some(Address::Inet(IpAddr::V4(ip)))
+        }
+        libc::AF_INET6 => {
+            let sockaddr = &*(sockaddr as *const libc::sockaddr_in6);
+            let ip = Ipv6Addr::from(sockaddr.sin6_addr.s6_addr);
+            Some(Address::Inet(IpAddr::V6(ip)))
+        }
+        libc::AF_PACKET => {
+            let sockaddr = &*(sockaddr as *const libc::sockaddr_ll);
+            let len = (sockaddr.sll_halen as usize).min(sockaddr.sll_addr.len());
+            Some(Address::Link(sockaddr.sll_addr[..len].to_vec()))
+        }
+        _ => None,
+    }
+}
+
+/// Returns the status of each network interface
+///
+/// `/sys/class/net` contains a directory for each interface. Virtual interfaces don't report a
+/// speed or duplex mode, in which case they are returned as 0 and `Duplex::Unknown`.
+pub fn net_if_stats() -> Result<HashMap<String, NicStats>> {
+    let mut nics = HashMap::new();
+
+    for entry in read_dir("/sys/class/net")? {
+        let path = entry?.path();
+        let name = match path.file_name() {
+            Some(name) => name.to_string_lossy().into_owned(),
+            None => continue,
+        };
+
+        // The interface may have been removed since the directory was listed
+        let (flags, mtu) = match (read_file(&path.join("flags")), read_file(&path.join("mtu"))) {
+            (Ok(flags), Ok(mtu)) => (flags, mtu),
+            (Err(ref e), _) | (_, Err(ref e)) if e.kind() == ErrorKind::NotFound => continue,
+            (Err(e), _) | (_, Err(e)) => return Err(e),
+        };
+
+        // Reading speed and duplex fails with EINVAL for interfaces that don't support them
+        let speed = read_file(&path.join("speed")).unwrap_or_default();
+        let duplex = read_file(&path.join("duplex")).unwrap_or_default();
+
+        nics.insert(name, nic_stats_internal(&flags, &mtu, &speed, &duplex)?);
+    }
+
+    Ok(nics)
+}
+
+/// Parses the contents of the `flags`, `mtu`, `speed` and `duplex` files of an interface.
+fn nic_stats_internal(flags: &str, mtu: &str, speed: &str, duplex: &str) -> Result<NicStats> {
+    let flags = i64::from_str_radix(flags.trim().trim_start_matches("0x"), 16).map_err(|_| {
+        Error::new(
+            ErrorKind::InvalidData,
+            format!("Could not parse {:?}", flags),
+        )
+    })?;
+
+    Ok(NicStats {
+        isup: flags & i64::from(libc::IFF_UP) != 0,
+        duplex: match duplex.trim() {
+            "full" => Duplex::Full,
+            "half" => Duplex::Half,
+            _ => Duplex::Unknown,
+        },
+        // Speed is -1 when the link is down
+        speed: speed.trim().parse().unwrap_or(0),
+        mtu: try_parse!(mtu.trim()),
+    })
+}
+
+/// Returns the time the system was booted
+///
+/// `/proc/stat` contains the boot time as the number of seconds since the Unix epoch
+pub fn boot_time() -> Result<SystemTime> {
+    let data = read_file(Path::new("/proc/stat"))?;
+    Ok(UNIX_EPOCH + Duration::from_secs(boot_time_internal(&data)?))
+}
+
+fn boot_time_internal(data: &str) -> Result<u64> {
+    for line in data.lines() {
+        if let Some(btime) = line.strip_prefix("btime ") {
+            return Ok(try_parse!(btime.trim()));
+        }
+    }
+    Err(not_found("btime"))
+}
+
+/// Returns the users currently logged in
+///
+/// `/var/run/utmp` contains a record for each login session. If the file doesn't exist, no users
+/// are returned.
+pub fn users() -> Result<Vec<User>> {
+    match fs::read("/var/run/utmp") {
+        Ok(data) => Ok(users_internal(&data)),
+        Err(ref e) if e.kind() == ErrorKind::NotFound => Ok(Vec::new()),
+        Err(e) => Err(e),
+    }
+}
+
+/// Parses the `utmp` records in `data`, returning those for user processes.
+///
+/// The records are in the same format as `struct utmpx`, which varies between architectures.
+fn users_internal(data: &[u8]) -> Vec<User> {
+    let mut users = Vec::new();
+
+    for record in data.chunks(mem::size_of::<libc::utmpx>()) {
+        if record.len() != mem::size_of::<libc::utmpx>() {
+            break;
+        }
+        let entry = unsafe { ptr::read_unaligned(record.as_ptr() as *const libc::utmpx) };
+        // Times before the epoch can only come from a corrupt record
+        if entry.ut_type != libc::USER_PROCESS
+            || entry.ut_tv.tv_sec < 0
+            || entry.ut_tv.tv_usec < 0
+        {
+            continue;
+        }
+
+        let started = UNIX_EPOCH
+            + Duration::from_secs(entry.ut_tv.tv_sec as u64)
+            + Duration::from_micros(entry.ut_tv.tv_usec as u64);
+
+        users.push(User {
+            name: c_chars_to_string(&entry.ut_user),
+            terminal: Some(c_chars_to_string(&entry.ut_line)).filter(|s| !s.is_empty()),
+            host: Some(c_chars_to_string(&entry.ut_host)).filter(|s| !s.is_empty()),
+            started,
+            pid: entry.ut_pid,
+        });
+    }
+
+    users
+}
+
+/// Converts a fixed size, NUL padded C string to a `String`.
+fn c_chars_to_string(chars: &[libc::c_char]) -> String {
+    let bytes: Vec<u8> = chars
+        .iter()
+        .take_while(|&&c| c != 0)
+        .map(|&c| c as u8)
+        .collect();
+    String::from_utf8_lossy(&bytes).into_owned()
+}
+
 #[cfg(test)]
 mod unit_tests {
     use super::*;
+    use std::ffi::OsStr;
 
     #[test]
     fn uptime_parses() {
@@ -197,6 +1534,344 @@ mod unit_tests {
         assert_eq!(out.last_pid, 1454);
     }
 
+    #[test]
+    fn cpu_times_parses() {
+        let input = "cpu  4705 150 1120 16250 520 0 32 0 0 0\n\
+                     cpu0 2356 75 565 8120 260 0 16 0 0 0\n\
+                     cpu1 2349 75 555 8130 260 0 16 0 0 0\n\
+                     intr 114930548 113199788 3 0 5 263 0 4 [... lots more numbers ...]\n\
+                     ctxt 1990473\n\
+                     btime 1062191376\n\
+                     processes 2915\n";
+        let (total, percpu) = cpu_times_internal(input).unwrap();
+        assert_eq!(percpu.len(), 2);
+        assert_eq!(total.user, 4705.0 / *TICKS_PER_SECOND);
+        assert_eq!(total.idle, 16250.0 / *TICKS_PER_SECOND);
+        assert_eq!(percpu[1].system, 555.0 / *TICKS_PER_SECOND);
+        assert_eq!(percpu[1].softirq, 16.0 / *TICKS_PER_SECOND);
+    }
+
+    #[test]
+    fn cpu_times_old_kernel() {
+        // Kernels before 2.6.24 don't report guest or guest_nice
+        let input = "cpu  4705 150 1120 16250 520 0 32 7\ncpu0 4705 150 1120 16250 520 0 32 7\n";
+        let (total, _) = cpu_times_internal(input).unwrap();
+        assert_eq!(total.steal, 7.0 / *TICKS_PER_SECOND);
+        assert_eq!(total.guest, 0.0);
+        assert_eq!(total.guest_nice, 0.0);
+    }
+
+    #[test]
+    fn cpu_times_error() {
+        assert!(cpu_times_internal("cpu  1 2 3\n").is_err());
+        assert!(cpu_times_internal("intr 1 2 3\n").is_err());
+    }
+
+    #[test]
+    fn cpu_percent_calculates() {
+        let before = CpuTimes {
+            user: 10.0,
+            system: 10.0,
+            idle: 70.0,
+            iowait: 10.0,
+            ..CpuTimes::default()
+        };
+        let after = CpuTimes {
+            user: 40.0,
+            system: 20.0,
+            idle: 120.0,
+            iowait: 20.0,
+            ..CpuTimes::default()
+        };
+        assert_eq!(busy_percent(&before, &after), 40.0);
+
+        let percent = CpuTimesPercent::new(&before, &after);
+        assert_eq!(percent.user, 30.0);
+        assert_eq!(percent.system, 10.0);
+        assert_eq!(percent.idle, 50.0);
+        assert_eq!(percent.iowait, 10.0);
+    }
+
+    #[test]
+    fn cpu_percent_empty_interval() {
+        let times = CpuTimes {
+            user: 10.0,
+            idle: 70.0,
+            ..CpuTimes::default()
+        };
+        assert_eq!(busy_percent(&times, &times), 0.0);
+        assert_eq!(CpuTimesPercent::new(&times, &times).idle, 0.0);
+    }
+
+    #[test]
+    fn disk_io_counters_parses() {
+        let input = "8       0 sda 5889 3858 2030338 8411 3261 3262 607448 2697 0 3284 11618 1495 0 428216 506 37 2\n\
+                     8       1 sda1 5801 3858 2026034 8390 3261 3262 607448 2697 0 3276 11088 0 0 0 0 0 0\n\
+                     8      16 sdb 6 31 290 0 0 0 0 0 0 0 0\n";
+        let disks = disk_io_counters_internal(input).unwrap();
+        assert_eq!(disks.len(), 3);
+
+        let (ref name, ref sda) = disks[0];
+        assert_eq!(name, "sda");
+        assert_eq!(sda.read_count, 5889);
+        assert_eq!(sda.read_merged_count, 3858);
+        assert_eq!(sda.read_bytes, 2030338 * 512);
+        assert_eq!(sda.read_time, 8411);
+        assert_eq!(sda.write_count, 3261);
+        assert_eq!(sda.write_merged_count, 3262);
+        assert_eq!(sda.write_bytes, 607448 * 512);
+        assert_eq!(sda.write_time, 2697);
+        assert_eq!(sda.busy_time, 3284);
+
+        assert_eq!(disks[1].0, "sda1");
+        // Sectors are 512 bytes even if the device has 4096 byte sectors
+        assert_eq!(disks[2].1.read_bytes, 290 * 512);
+    }
+
+    #[test]
+    fn disk_io_counters_old_partition_format() {
+        let input = "   3    1 hda1 35486 38030 38030 38030\n";
+        let disks = disk_io_counters_internal(input).unwrap();
+        assert_eq!(disks[0].1.read_count, 35486);
+        assert_eq!(disks[0].1.read_bytes, 38030 * 512);
+        assert_eq!(disks[0].1.write_count, 38030);
+        assert_eq!(disks[0].1.busy_time, 0);
+    }
+
+    #[test]
+    fn disk_io_counters_error() {
+        assert!(disk_io_counters_internal("   8       0 sda 1 2 3 4 5\n").is_err());
+        assert!(disk_io_counters_internal("   8       0 sda 1 2 x 4\n").is_err());
+    }
+
+    #[test]
+    fn disk_partitions_parses() {
+        let input = b"23 28 0:22 / /proc rw,relatime - proc proc rw\n\
+                      36 35 98:0 /mnt1 /mnt/my\\040disk rw,noatime master:1 - ext3 /dev/root rw\n\
+                      37 35 98:1 / /mnt/\xff rw - ext4 /dev/sdb1 rw\n";
+        let partitions = disk_partitions_internal(input).unwrap();
+        assert_eq!(partitions.len(), 3);
+        assert_eq!(partitions[0].device, "proc");
+        assert_eq!(partitions[0].fstype, "proc");
+        assert_eq!(partitions[1].device, "/dev/root");
+        assert_eq!(partitions[1].mountpoint, PathBuf::from("/mnt/my disk"));
+        assert_eq!(partitions[1].fstype, "ext3");
+        assert_eq!(partitions[1].opts, "rw,noatime");
+        assert_eq!(partitions[2].mountpoint, PathBuf::from(OsStr::from_bytes(b"/mnt/\xff")));
+    }
+
+    #[test]
+    fn disk_partitions_error() {
+        assert!(disk_partitions_internal(b"23 28 0:22 / /proc rw,relatime\n").is_err());
+        assert!(disk_partitions_internal(b"23 28 0:22 / /proc rw,relatime - proc\n").is_err());
+    }
+
+    #[test]
+    fn physical_filesystems_parses() {
+        let input = "nodev\tsysfs\nnodev\ttmpfs\n\text3\n\text4\nnodev\tproc\n";
+        assert_eq!(physical_filesystems(input), vec!["ext3", "ext4"]);
+    }
+
+    #[test]
+    fn unescape_octal_decodes() {
+        assert_eq!(unescape_octal(b"/mnt/a\\040b\\011c"), b"/mnt/a b\tc");
+        assert_eq!(unescape_octal(b"/mnt/back\\134slash"), b"/mnt/back\\slash");
+        assert_eq!(unescape_octal(b"/mnt/trailing\\04"), b"/mnt/trailing\\04");
+        assert_eq!(unescape_octal(b"/mnt/\\\xc3\xa912"), b"/mnt/\\\xc3\xa912");
+        assert_eq!(unescape_octal(b"/mnt/\\377"), b"/mnt/\xff");
+    }
+
+    #[test]
+    fn disk_usage_percent() {
+        let usage = DiskUsage::new(1000, 450, 450);
+        assert_eq!(usage.percent, 50.0);
+        assert_eq!(DiskUsage::new(0, 0, 0).percent, 0.0);
+    }
+
+    #[test]
+    fn net_io_counters_parses() {
+        let input = "Inter-|   Receive                                                |  Transmit\n \
+                     face |bytes    packets errs drop fifo frame compressed multicast|bytes    packets errs drop fifo colls carrier compressed\n    \
+                     lo: 13251901    2895    0    0    0     0          0         0 13251901    2895    0    0    0     0       0          0\n  \
+                     eth0: 1946317  15212    1    2    0     0          0        40   821405    6932    3    4    0     0       0          0\n";
+        let nics = net_io_counters_internal(input).unwrap();
+        assert_eq!(nics.len(), 2);
+        assert_eq!(nics[0].0, "lo");
+        assert_eq!(nics[0].1.bytes_recv, 13251901);
+
+        let (ref name, ref eth0) = nics[1];
+        assert_eq!(name, "eth0");
+        assert_eq!(eth0.bytes_recv, 1946317);
+        assert_eq!(eth0.packets_recv, 15212);
+        assert_eq!(eth0.errin, 1);
+        assert_eq!(eth0.dropin, 2);
+        assert_eq!(eth0.bytes_sent, 821405);
+        assert_eq!(eth0.packets_sent, 6932);
+        assert_eq!(eth0.errout, 3);
+        assert_eq!(eth0.dropout, 4);
+    }
+
+    #[test]
+    fn net_io_counters_no_space_after_name() {
+        let input = "Inter-|   Receive                            |  Transmit\n \
+                     face |bytes    packets errs drop fifo frame|bytes    packets errs drop fifo colls\n  \
+                     eth0:4294967296 1 0 0 0 0 20 2 0 0 0 0\n";
+        let nics = net_io_counters_internal(input).unwrap();
+        assert_eq!(nics[0].0, "eth0");
+        assert_eq!(nics[0].1.bytes_recv, 4294967296);
+        assert_eq!(nics[0].1.bytes_sent, 20);
+    }
+
+    #[test]
+    fn net_io_counters_error() {
+        let header = "Inter-|   Receive  |  Transmit\n face |bytes packets errs drop|bytes packets errs drop\n";
+        assert!(net_io_counters_internal(&format!("{}  eth0: 1 2 3\n", header)).is_err());
+        assert!(net_io_counters_internal(&format!("{}  eth0 1 2 3 4 5 6 7 8\n", header)).is_err());
+        assert!(net_io_counters_internal("Inter-|\n face |bytes\n").is_err());
+    }
+
+    // Addresses in /proc/net/tcp are in host byte order, so these fixtures only make sense on
+    // little-endian machines.
+    #[test]
+    #[cfg(target_endian = "little")]
+    fn parse_inet_table_tcp4() {
+        let input = "  sl  local_address rem_address   st tx_queue rx_queue tr tm->when retrnsmt   uid  timeout inode\n   \
+                     0: 0100007F:1F90 00000000:0000 0A 00000000:00000000 00:00000000 00000000  1000        0 925 1 0000000000000000 100 0 0 10 0\n   \
+                     1: 0F02000A:A4C6 22D8B85D:01BB 01 00000000:00000000 02:000A7C5D 00000000  1000        0 31337 2 0000000000000000 20 4 30 10 -1\n";
+        let connections = parse_inet_table(input, AddressFamily::Inet, SocketType::Stream).unwrap();
+        assert_eq!(connections.len(), 2);
+
+        let listen = &connections[0];
+        assert_eq!(listen.local_addr, Some("127.0.0.1:8080".parse().unwrap()));
+        assert_eq!(listen.remote_addr, None);
+        assert_eq!(listen.status, Some(TcpState::Listen));
+        assert_eq!(listen.inode, 925);
+
+        let established = &connections[1];
+        assert_eq!(established.local_addr, Some("10.0.2.15:42182".parse().unwrap()));
+        assert_eq!(established.remote_addr, Some("93.184.216.34:443".parse().unwrap()));
+        assert_eq!(established.status, Some(TcpState::Established));
+        assert_eq!(established.inode, 31337);
+    }
+
+    #[test]
+    #[cfg(target_endian = "little")]
+    fn parse_inet_table_udp6() {
+        let input = "  sl  local_address                         remote_address                        st tx_queue rx_queue tr tm->when retrnsmt   uid  timeout inode ref pointer drops\n  \
+                     12: 00000000000000000000000001000000:0035 00000000000000000000000000000000:0000 07 00000000:00000000 00:00000000 00000000     0        0 15470 2 0000000000000000 0\n";
+        let connections = parse_inet_table(input, AddressFamily::Inet6, SocketType::Datagram).unwrap();
+        assert_eq!(connections[0].family, AddressFamily::Inet6);
+        assert_eq!(connections[0].local_addr, Some("[::1]:53".parse().unwrap()));
+        assert_eq!(connections[0].remote_addr, None);
+        assert_eq!(connections[0].status, None);
+    }
+
+    #[test]
+    fn parse_inet_table_error() {
+        let header = "  sl  local_address rem_address   st tx_queue rx_queue tr tm->when retrnsmt   uid  timeout inode\n";
+        let bad_state = "   0: 0100007F:1F90 00000000:0000 FF 00000000:00000000 00:00000000 00000000  1000        0 925\n";
+        let bad_addr = "   0: 0100007F 00000000:0000 0A 00000000:00000000 00:00000000 00000000  1000        0 925\n";
+        for line in &[bad_state, bad_addr] {
+            let input = format!("{}{}", header, line);
+            assert!(parse_inet_table(&input, AddressFamily::Inet, SocketType::Stream).is_err());
+        }
+    }
+
+    #[test]
+    fn parse_unix_table_parses() {
+        let input = b"Num       RefCount Protocol Flags    Type St Inode Path\n\
+                      0000000017f4b01d: 00000003 00000000 00000000 0001 03   924\n\
+                      0000000040ed8560: 00000002 00000000 00010000 0001 01  8217 /run/my socket\n\
+                      0000000040ed8561: 00000002 00000000 00000000 0002 01  8218 @/tmp/dbus-Xq3pZ\n\
+                      0000000040ed8562: 00000002 00000000 00010000 0001 01  8219 /run/\xff.sock\n";
+        let connections = parse_unix_table(input).unwrap();
+        assert_eq!(connections.len(), 4);
+        assert_eq!(connections[0].path, None);
+        assert_eq!(connections[0].inode, 924);
+        assert_eq!(connections[1].socket_type, SocketType::Stream);
+        assert_eq!(connections[1].path, Some(PathBuf::from("/run/my socket")));
+        assert_eq!(connections[2].socket_type, SocketType::Datagram);
+        assert_eq!(connections[2].path, Some(PathBuf::from("@/tmp/dbus-Xq3pZ")));
+        assert_eq!(connections[3].inode, 8219);
+        assert_eq!(connections[3].path, Some(PathBuf::from(OsStr::from_bytes(b"/run/\xff.sock"))));
+    }
+
+    #[test]
+    fn address_display() {
+        let mac = Address::Link(vec![0x52, 0x54, 0x00, 0x12, 0x34, 0x5e]);
+        assert_eq!(mac.to_string(), "52:54:00:12:34:5e");
+        let ip = Address::Inet("fe80::1".parse().unwrap());
+        assert_eq!(ip.to_string(), "fe80::1");
+    }
+
+    #[test]
+    fn nic_stats_parses() {
+        let stats = nic_stats_internal("0x1003\n", "1500\n", "1000\n", "full\n").unwrap();
+        assert!(stats.isup);
+        assert_eq!(stats.duplex, Duplex::Full);
+        assert_eq!(stats.speed, 1000);
+        assert_eq!(stats.mtu, 1500);
+
+        // A link that is down reports a speed of -1, and virtual interfaces report neither
+        let stats = nic_stats_internal("0x1002\n", "65536\n", "-1\n", "").unwrap();
+        assert!(!stats.isup);
+        assert_eq!(stats.duplex, Duplex::Unknown);
+        assert_eq!(stats.speed, 0);
+        assert_eq!(stats.mtu, 65536);
+    }
+
+    #[test]
+    fn nic_stats_error() {
+        assert!(nic_stats_internal("up\n", "1500\n", "", "").is_err());
+        assert!(nic_stats_internal("0x1003\n", "\n", "", "").is_err());
+    }
+
+    #[test]
+    fn boot_time_parses() {
+        let input = "cpu  4705 150 1120 16250 520 0 32 0 0 0\nctxt 1990473\nbtime 1062191376\n";
+        assert_eq!(boot_time_internal(input).unwrap(), 1062191376);
+        assert!(boot_time_internal("cpu  4705 150 1120 16250 520 0 32 0 0 0\n").is_err());
+    }
+
+    /// `/var/run/utmp` written by `utmpdump -r` on x86_64 glibc, containing boot, runlevel,
+    /// login, user and dead process records.
+    #[cfg(all(target_env = "gnu", target_arch = "x86_64"))]
+    const UTMP_FIXTURE: &[u8] = include_bytes!("../tests/fixtures/utmp");
+
+    #[test]
+    #[cfg(all(target_env = "gnu", target_arch = "x86_64"))]
+    fn users_parses() {
+        let users = users_internal(UTMP_FIXTURE);
+        assert_eq!(users.len(), 2);
+        assert_eq!(users[0].name, "alice");
+        assert_eq!(users[0].terminal, Some("pts/0".to_string()));
+        assert_eq!(users[0].host, Some("10.0.0.1".to_string()));
+        assert_eq!(users[0].pid, 1234);
+        assert_eq!(users[0].started, UNIX_EPOCH + Duration::from_millis(1500000000500));
+        assert_eq!(users[1].name, "bob");
+        assert_eq!(users[1].terminal, Some("tty2".to_string()));
+        assert_eq!(users[1].host, None);
+    }
+
+    #[test]
+    #[cfg(all(target_env = "gnu", target_arch = "x86_64"))]
+    fn users_skips_invalid_records() {
+        // Set the login time of alice's record (the fourth) to before the epoch
+        let mut input = UTMP_FIXTURE.to_vec();
+        let record = input[3 * mem::size_of::<libc::utmpx>()..].as_mut_ptr() as *mut libc::utmpx;
+        unsafe {
+            let mut entry = ptr::read_unaligned(record);
+            entry.ut_tv.tv_sec = -1;
+            ptr::write_unaligned(record, entry);
+        }
+        // Trailing partial records are ignored
+        input.extend_from_slice(&[0; 10]);
+
+        let users = users_internal(&input);
+        assert_eq!(users.len(), 1);
+        assert_eq!(users[0].name, "bob");
+    }
+
     #[test]
     fn make_map_spaces() {
         let input = "field1: 23\nfield2: 45\nfield3: 100\n";
@@ -1,9 +1,48 @@
+extern crate libc;
 extern crate psutil;
+extern crate tempdir;
+
+use std::env;
+use std::fs;
+use std::os::unix::fs::PermissionsExt;
+use std::os::unix::process::CommandExt;
+use std::path::Path;
+use std::process::{Child, Command};
+use std::time::Duration;
+
+use tempdir::TempDir;
+
+use psutil::process::{IoClass, IoNice, Process, RLimit, Resource, Signal, State};
 
 fn get_process() -> psutil::process::Process {
     psutil::process::Process::new(psutil::getpid()).unwrap()
 }
 
+/// Spawn a child process, returning it once the kernel has finished exec'ing it.
+///
+/// `spawn` returns as soon as `execve` releases the parent's address space, which is before the
+/// kernel has set up the new argument and environment pages, so the cmdline and environ can
+/// briefly read as empty.
+fn spawn(command: &mut Command) -> (Child, Process) {
+    let mut child = command.spawn().unwrap();
+    let pid = child.id() as psutil::PID;
+    for _ in 0..100 {
+        let process = Process::new(pid).unwrap();
+        if process.cmdline_os().unwrap().is_some() {
+            return (child, process);
+        }
+        std::thread::sleep(Duration::from_millis(10));
+    }
+    child.kill().unwrap();
+    child.wait().unwrap();
+    panic!("process {} was not exec'd", pid);
+}
+
+/// Spawn `sleep 10`, which must be killed and waited for by the test.
+fn spawn_sleep() -> (Child, Process) {
+    spawn(Command::new("sleep").arg("10"))
+}
+
 #[test]
 fn process_alive() {
     assert!(get_process().is_alive());
@@ -18,11 +57,34 @@ fn process_cpu() {
     assert!(process.cstime >= 0.0);
 }
 
+#[test]
+fn process_cpu_percent() {
+    let mut process = get_process();
+    let percent = process.cpu_percent().unwrap();
+    assert!((0.0..=100.0).contains(&percent));
+}
+
 #[test]
 fn process_cmdline() {
     assert!(get_process().cmdline().is_ok());
 }
 
+#[test]
+fn process_cmdline_os() {
+    // The shell runs `sleep` as a child, so put both in a process group that can be killed at once
+    let (mut child, process) = spawn(Command::new("sh")
+        .args(["-c", "sleep 10; true", "my job"])
+        .process_group(0));
+
+    let args = process.cmdline_vec().unwrap().unwrap();
+    assert_eq!(args, vec!["sh", "-c", "sleep 10; true", "my job"]);
+    assert_eq!(process.cmdline().unwrap().unwrap(), "sh -c 'sleep 10; true' 'my job'");
+    assert_eq!(process.cmdline_os().unwrap().unwrap().len(), 4);
+
+    assert_eq!(unsafe { libc::kill(-process.pid, libc::SIGKILL) }, 0);
+    child.wait().unwrap();
+}
+
 #[test]
 fn process_cwd() {
     assert!(get_process().cwd().is_ok());
@@ -38,6 +100,305 @@ fn process_memory() {
     get_process().memory().unwrap();
 }
 
+#[test]
+fn process_connections() {
+    use psutil::system::ConnectionKind;
+
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+
+    let connections = get_process().connections(ConnectionKind::Tcp).unwrap();
+    let connection = connections.iter().find(|c| c.local_addr == Some(addr)).unwrap();
+    assert!(connection.fd.is_some());
+
+    let udp = get_process().connections(ConnectionKind::Udp).unwrap();
+    assert!(udp.iter().all(|c| c.local_addr != Some(addr)));
+}
+
+#[test]
+fn process_create_time() {
+    let create_time = get_process().create_time().unwrap();
+    assert!(create_time >= psutil::system::boot_time().unwrap());
+    assert!(create_time <= std::time::SystemTime::now());
+}
+
+#[test]
+fn process_key() {
+    let key = get_process().key().unwrap();
+    assert_eq!(key.pid, psutil::getpid());
+    assert_eq!(key.boot_id.len(), 36);
+    assert_eq!(key, get_process().key().unwrap());
+    assert_eq!(key.to_string().parse::<psutil::process::ProcessKey>().unwrap(), key);
+}
+
+#[test]
+fn process_status() {
+    let process = get_process();
+    let status = process.status().unwrap();
+    assert_eq!(status.pid, process.pid);
+    assert_eq!(status.ppid, process.ppid);
+    assert!(status.vm_hwm.is_some());
+    assert!(!status.cpus_allowed_list.is_empty());
+}
+
+#[test]
+fn process_uids_gids() {
+    let process = get_process();
+    assert_eq!(process.uids().unwrap().real, process.uid);
+    assert_eq!(process.gids().unwrap().real, process.gid);
+}
+
+#[test]
+fn process_username() {
+    assert!(!get_process().username().unwrap().is_empty());
+}
+
+#[test]
+fn process_memory_full_info() {
+    let memory = get_process().memory_full_info().unwrap();
+    assert!(memory.rss > 0);
+    assert!(memory.uss <= memory.rss);
+    assert!(memory.pss <= memory.rss);
+}
+
+#[test]
+fn process_memory_maps() {
+    let process = get_process();
+    let maps = process.memory_maps(false).unwrap();
+    assert!(maps.iter().all(|map| map.region.is_some()));
+    assert!(maps.iter().any(|map| map.path == Path::new("[stack]")));
+
+    let grouped = process.memory_maps(true).unwrap();
+    assert!(grouped.len() <= maps.len());
+    assert!(grouped.iter().all(|map| map.region.is_none()));
+}
+
+#[test]
+fn process_io_counters() {
+    let io = get_process().io_counters().unwrap();
+    assert!(io.rchar > 0);
+    assert!(io.syscr > 0);
+}
+
+#[test]
+fn process_io_counters_permission_denied() {
+    // Reading another user's `io` requires ptrace access, which root always has, so this test runs
+    // itself again as `nobody` and checks the error there
+    if env::var_os("PSUTIL_TEST_IO_DENIED").is_some() {
+        let error = Process::new(1).unwrap().io_counters().unwrap_err();
+        assert_eq!(error.raw_os_error(), Some(libc::EACCES));
+        return;
+    }
+
+    // The test binary may be in a directory `nobody` can't access, so run a copy of it
+    let tempdir = TempDir::new("psutil-tests").unwrap();
+    fs::set_permissions(tempdir.path(), fs::Permissions::from_mode(0o755)).unwrap();
+    let exe = tempdir.path().join("process-tests");
+    fs::copy(env::current_exe().unwrap(), &exe).unwrap();
+
+    let mut command = Command::new(&exe);
+    command.args(["--exact", "process_io_counters_permission_denied"])
+        .env("PSUTIL_TEST_IO_DENIED", "1");
+    if unsafe { libc::geteuid() } == 0 {
+        command.uid(65534).gid(65534);
+    }
+
+    let output = loop {
+        match command.output() {
+            // Switching user needs CAP_SETUID, which root may lack in a container
+            Err(ref e) if e.raw_os_error() == Some(libc::EPERM) => return,
+            // Another test may fork while the copy is still open for writing
+            Err(ref e) if e.raw_os_error() == Some(libc::ETXTBSY) => {
+                std::thread::sleep(Duration::from_millis(10))
+            }
+            output => break output.unwrap(),
+        }
+    };
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stdout));
+}
+
+#[test]
+fn process_threads() {
+    let (started, wait_started) = std::sync::mpsc::channel();
+    let (stop, wait_stop) = std::sync::mpsc::channel::<()>();
+    let handle = std::thread::Builder::new()
+        .name("psutil-test".to_string())
+        .spawn(move || {
+            started.send(()).unwrap();
+            wait_stop.recv().unwrap_or(());
+        })
+        .unwrap();
+    wait_started.recv().unwrap();
+
+    let process = get_process();
+    let threads = process.threads().unwrap();
+    assert!(threads.iter().any(|t| t.tid == process.pid));
+    assert!(threads.iter().any(|t| t.name == "psutil-test"));
+
+    stop.send(()).unwrap();
+    handle.join().unwrap();
+}
+
+#[test]
+fn process_parent() {
+    let parent = get_process().parent().unwrap().unwrap();
+    assert_eq!(parent.pid, psutil::getppid());
+}
+
+#[test]
+fn process_children() {
+    let (mut child, _) = spawn_sleep();
+    let pid = child.id() as psutil::PID;
+
+    let process = get_process();
+    assert!(process.children(false).unwrap().iter().any(|p| p.pid == pid));
+    assert!(process.children(true).unwrap().iter().any(|p| p.pid == pid));
+
+    let tree = psutil::process::ProcessTree::new().unwrap();
+    assert!(tree.descendants(process.pid).iter().any(|p| p.pid == pid));
+    assert_eq!(tree.parent(pid).map(|p| p.pid), Some(process.pid));
+
+    child.kill().unwrap();
+    child.wait().unwrap();
+}
+
+fn wait_for_state(process: &Process, stopped: bool) {
+    for _ in 0..100 {
+        let state = Process::new(process.pid).unwrap().state;
+        if matches!(state, State::Stopped) == stopped {
+            return;
+        }
+        std::thread::sleep(std::time::Duration::from_millis(10));
+    }
+    panic!("process {} did not change state", process.pid);
+}
+
+#[test]
+fn process_signals() {
+    let (mut child, process) = spawn_sleep();
+
+    process.suspend().unwrap();
+    wait_for_state(&process, true);
+    process.resume().unwrap();
+    wait_for_state(&process, false);
+
+    process.send_signal(Signal::Term).unwrap();
+    let status = child.wait().unwrap();
+    assert_eq!(std::os::unix::process::ExitStatusExt::signal(&status), Some(15));
+
+    // The process is a zombie until it is reaped, and then it no longer exists
+    assert!(process.terminate().is_err());
+}
+
+#[test]
+fn process_wait_child() {
+    let pid = Command::new("sh").args(["-c", "exit 3"]).spawn().unwrap().id();
+    let process = Process::new(pid as psutil::PID).unwrap();
+    assert_eq!(process.wait(Some(Duration::from_secs(10))).unwrap(), Some(3));
+
+    let (_child, process) = spawn_sleep();
+    let error = process.wait(Some(Duration::from_millis(50))).unwrap_err();
+    assert_eq!(error.kind(), std::io::ErrorKind::TimedOut);
+
+    process.kill().unwrap();
+    assert_eq!(process.wait(None).unwrap(), Some(-9));
+}
+
+#[test]
+fn process_wait_non_child() {
+    use std::io::BufRead;
+
+    // The shell is replaced by a sleep that never reaps its background child, leaving a zombie
+    // that we can't reap ourselves
+    let mut parent = Command::new("sh")
+        .args(["-c", "sh -c 'exit 7' & echo $!; exec sleep 10"])
+        .stdout(std::process::Stdio::piped())
+        .spawn()
+        .unwrap();
+    let mut line = String::new();
+    std::io::BufReader::new(parent.stdout.take().unwrap()).read_line(&mut line).unwrap();
+    let pid = line.trim().parse().unwrap();
+
+    let process = Process::new(pid).unwrap();
+    assert_eq!(process.wait(Some(Duration::from_secs(10))).unwrap(), Some(7));
+
+    parent.kill().unwrap();
+    parent.wait().unwrap();
+}
+
+#[test]
+fn wait_procs() {
+    let mut processes = Vec::new();
+    for _ in 0..2 {
+        let (_child, process) = spawn_sleep();
+        processes.push(process);
+    }
+    processes[0].terminate().unwrap();
+
+    let mut exited = Vec::new();
+    let (gone, alive) = psutil::process::wait_procs(&processes,
+                                                    Some(Duration::from_millis(200)),
+                                                    |p, code| exited.push((p.pid, code)))
+        .unwrap();
+    assert_eq!(exited, vec![(processes[0].pid, Some(-15))]);
+    assert_eq!(gone.len(), 1);
+    assert_eq!(alive, vec![processes[1].clone()]);
+
+    alive[0].kill().unwrap();
+    alive[0].wait(None).unwrap();
+}
+
+#[test]
+fn process_scheduling() {
+    let (_child, process) = spawn_sleep();
+
+    process.set_nice(process.nice as i32 + 1).unwrap();
+    assert_eq!(Process::new(process.pid).unwrap().nice, process.nice + 1);
+
+    let ionice = IoNice { class: IoClass::Idle, value: 0 };
+    process.set_ionice(ionice).unwrap();
+    assert_eq!(process.ionice().unwrap(), ionice);
+
+    let cpus = process.cpu_affinity().unwrap();
+    assert!(!cpus.is_empty());
+    process.set_cpu_affinity(&cpus[..1]).unwrap();
+    assert_eq!(process.cpu_affinity().unwrap(), &cpus[..1]);
+    assert!(process.set_cpu_affinity(&[usize::MAX]).is_err());
+
+    let limit = RLimit { soft: Some(64), hard: Some(128) };
+    process.set_rlimit(Resource::Nofile, limit).unwrap();
+    assert_eq!(process.rlimit(Resource::Nofile).unwrap(), limit);
+
+    process.kill().unwrap();
+    process.wait(None).unwrap();
+    assert_eq!(process.rlimit(Resource::Nofile).unwrap_err().kind(),
+               std::io::ErrorKind::NotFound);
+}
+
+#[test]
+fn process_terminal() {
+    let process = Process::new(psutil::getpid()).unwrap();
+    let terminal = process.terminal().unwrap();
+    if process.tty().is_none() {
+        assert_eq!(terminal, None);
+    }
+    if let Some(path) = terminal {
+        assert!(path.starts_with("/dev"));
+    }
+}
+
+#[test]
+fn process_environ_os() {
+    let (_child, process) = spawn(Command::new("sleep").arg("10").env("PSUTIL_TEST", "my value"));
+
+    let environ = process.environ_os().unwrap();
+    assert_eq!(environ.get("PSUTIL_TEST").unwrap(), "my value");
+    assert!(environ.malformed.is_empty());
+
+    process.kill().unwrap();
+    process.wait(None).unwrap();
+}
+
 #[test]
 fn process_equality() {
     assert_eq!(get_process(), get_process());
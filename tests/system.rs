@@ -1,5 +1,7 @@
 extern crate psutil;
 
+use std::time::Duration;
+
 #[test]
 fn uptime() {
     assert!(psutil::system::uptime() > 0);
@@ -32,3 +34,122 @@ fn loadaverage() {
     assert!(load.five >= 0.0);
     assert!(load.fifteen >= 0.0);
 }
+
+#[test]
+fn cpu_count() {
+    assert!(psutil::system::cpu_count().unwrap() > 0);
+}
+
+#[test]
+fn cpu_times() {
+    let times = psutil::system::cpu_times().unwrap();
+    assert!(times.user >= 0.0);
+    assert!(times.idle > 0.0);
+}
+
+#[test]
+fn cpu_times_percpu() {
+    let percpu = psutil::system::cpu_times_percpu().unwrap();
+    assert!(!percpu.is_empty());
+}
+
+#[test]
+fn cpu_percent() {
+    let percent = psutil::system::cpu_percent(Duration::from_millis(100)).unwrap();
+    assert!((0.0..=100.0).contains(&percent));
+}
+
+#[test]
+fn cpu_percent_collector() {
+    let mut collector = psutil::system::CpuPercentCollector::new().unwrap();
+    std::thread::sleep(Duration::from_millis(100));
+
+    let percpu = collector.cpu_percent_percpu().unwrap();
+    assert_eq!(percpu.len(), psutil::system::cpu_times_percpu().unwrap().len());
+
+    let times = collector.cpu_times_percent().unwrap();
+    assert!((0.0..=100.0).contains(&times.idle));
+}
+
+#[test]
+fn disk_io_counters() {
+    // Counters only increase, so read the total last for it to be at least the sum of the earlier
+    // per-disk reading
+    let perdisk = psutil::system::disk_io_counters_perdisk(false).unwrap();
+    let total = psutil::system::disk_io_counters().unwrap();
+    let read_count = perdisk.values().map(|disk| disk.read_count).sum();
+    assert!(total.read_count >= read_count);
+}
+
+#[test]
+fn disk_io_counters_perdisk() {
+    let disks = psutil::system::disk_io_counters_perdisk(false).unwrap();
+    let partitions = psutil::system::disk_io_counters_perdisk(true).unwrap();
+    assert!(partitions.len() >= disks.len());
+}
+
+#[test]
+fn disk_partitions() {
+    let all = psutil::system::disk_partitions(true).unwrap();
+    let physical = psutil::system::disk_partitions(false).unwrap();
+    assert!(all.iter().any(|p| p.fstype == "proc"));
+    assert!(physical.iter().all(|p| p.fstype != "proc"));
+}
+
+#[test]
+fn disk_usage() {
+    let usage = psutil::system::disk_usage(std::path::Path::new("/")).unwrap();
+    assert!(usage.total > 0);
+    assert!(usage.used <= usage.total);
+    assert!((0.0..=100.0).contains(&usage.percent));
+}
+
+#[test]
+fn net_io_counters() {
+    let pernic = psutil::system::net_io_counters_pernic().unwrap();
+    let total = psutil::system::net_io_counters().unwrap();
+    assert!(pernic.contains_key("lo"));
+    assert!(total.bytes_recv >= pernic["lo"].bytes_recv);
+}
+
+#[test]
+fn net_connections() {
+    use psutil::system::{AddressFamily, ConnectionKind};
+
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+
+    let connections = psutil::system::net_connections(ConnectionKind::Tcp4).unwrap();
+    assert!(connections.iter().any(|c| c.local_addr == Some(addr)));
+
+    let unix = psutil::system::net_connections(ConnectionKind::Unix).unwrap();
+    assert!(unix.iter().all(|c| c.family == AddressFamily::Unix && c.status.is_none()));
+}
+
+#[test]
+fn net_if_addrs() {
+    use psutil::system::Address;
+
+    let nics = psutil::system::net_if_addrs().unwrap();
+    let localhost = Address::Inet("127.0.0.1".parse().unwrap());
+    assert!(nics["lo"].iter().any(|nic| nic.address == localhost));
+}
+
+#[test]
+fn net_if_stats() {
+    let nics = psutil::system::net_if_stats().unwrap();
+    assert!(nics["lo"].isup);
+    assert!(nics["lo"].mtu > 0);
+}
+
+#[test]
+fn boot_time() {
+    let boot_time = psutil::system::boot_time().unwrap();
+    assert!(boot_time < std::time::SystemTime::now());
+    assert!(boot_time > std::time::UNIX_EPOCH);
+}
+
+#[test]
+fn users() {
+    psutil::system::users().unwrap();
+}